@@ -0,0 +1,247 @@
+use std::io::{self, BufRead, Write};
+use std::thread;
+
+use crate::cli::{self, Failure, Format, Options, EXIT_PARSE, EXIT_SUCCESS};
+use diff_rs::json::{self, Value};
+
+struct Record {
+    id: Value,
+    source: Result<String, Failure>,
+}
+
+// With several workers, lines are read and differentiated in blocks of this
+// many per worker; a single worker handles one line at a time.
+const BLOCK: usize = 64;
+
+/// Differentiates every non-blank line of `input` and writes one result per
+/// line, in input order, as soon as it is ready. A line that fails produces
+/// an error record instead of stopping the run; the returned exit code is the
+/// highest of any line.
+pub fn run(input: impl BufRead, output: &mut impl Write, options: &Options) -> io::Result<i32> {
+    let block = if options.jobs <= 1 {
+        1
+    } else {
+        options.jobs * BLOCK
+    };
+    let mut code = EXIT_SUCCESS;
+    let mut records = vec![];
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let number = i as i64 + 1;
+        records.push(if options.jsonl {
+            read_record(&line, number)
+        } else {
+            Record {
+                id: Value::Int(number),
+                source: Ok(line),
+            }
+        });
+        if records.len() >= block {
+            code = code.max(write_block(&records, output, options)?);
+            records.clear();
+        }
+    }
+    code = code.max(write_block(&records, output, options)?);
+    Ok(code)
+}
+
+fn write_block(records: &[Record], output: &mut impl Write, options: &Options) -> io::Result<i32> {
+    let results: Vec<(String, i32)> = if options.jobs <= 1 {
+        records.iter().map(|r| render(r, options)).collect()
+    } else {
+        let chunk = records.len().div_ceil(options.jobs).max(1);
+        thread::scope(|scope| {
+            let workers: Vec<_> = records
+                .chunks(chunk)
+                .map(|chunk| {
+                    scope
+                        .spawn(move || chunk.iter().map(|r| render(r, options)).collect::<Vec<_>>())
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("batch worker panicked"))
+                .collect()
+        })
+    };
+
+    let mut code = EXIT_SUCCESS;
+    for (line, line_code) in results {
+        writeln!(output, "{}", line)?;
+        code = code.max(line_code);
+    }
+    output.flush()?;
+    Ok(code)
+}
+
+fn read_record(line: &str, number: i64) -> Record {
+    let failure = |kind, message| Record {
+        id: Value::Int(number),
+        source: Err(Failure {
+            kind,
+            message,
+            code: EXIT_PARSE,
+        }),
+    };
+    let value = match json::parse(line) {
+        Ok(value) => value,
+        Err(error) => return failure("json", error.to_string()),
+    };
+    let id = value.get("id").cloned().unwrap_or(Value::Int(number));
+    match value.get("expr").and_then(Value::as_str) {
+        Some(source) => Record {
+            id,
+            source: Ok(source.to_string()),
+        },
+        None => Record {
+            id,
+            ..failure("record", "record has no string `expr` member".to_string())
+        },
+    }
+}
+
+fn render(record: &Record, options: &Options) -> (String, i32) {
+    let result = match &record.source {
+        Ok(source) => cli::process(source, options),
+        Err(failure) => Err(failure.clone()),
+    };
+    match (result, options.format) {
+        (Ok(success), Format::Text | Format::Latex | Format::Sexpr) => match success.value {
+            Some(value) => (format!("{}\t{}", success.derivative, value), EXIT_SUCCESS),
+            None => (success.derivative, EXIT_SUCCESS),
+        },
+        (Err(failure), Format::Text | Format::Latex | Format::Sexpr) => {
+            (format!("error: {}", failure.message), failure.code)
+        }
+        (Ok(success), Format::Json) => {
+            let mut members = vec![
+                ("id".to_string(), record.id.clone()),
+                ("derivative".to_string(), Value::from(success.derivative)),
+            ];
+            if let Some(value) = success.value {
+                members.push(("value".to_string(), Value::from(value)));
+            }
+            (Value::Object(members).to_string(), EXIT_SUCCESS)
+        }
+        (Err(failure), Format::Json) => {
+            let record = Value::object([("id", record.id.clone()), ("error", failure.to_json())]);
+            (record.to_string(), failure.code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufReader, Read};
+
+    use crate::batch::run;
+    use crate::cli::{Format, Options, EXIT_DIFF, EXIT_DOMAIN, EXIT_PARSE, EXIT_SUCCESS};
+
+    fn options() -> Options {
+        Options {
+            expression: None,
+            format: Format::Text,
+            variable: "x".to_string(),
+            order: 1,
+            simplify: true,
+            eval: None,
+            batch: Some("-".to_string()),
+            jsonl: false,
+            jobs: 1,
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let cases = [
+            (
+                "x^2\n\nsin(x\nexp(2x)\n",
+                options(),
+                "2 * x\nerror: unclosed `(` at column 4\n2 * exp(2 * x)\n",
+                EXIT_PARSE,
+            ),
+            (
+                "x^2\nlog(x)\n",
+                Options {
+                    eval: Some(0.0),
+                    ..options()
+                },
+                "2 * x\t0\nerror: `pow` is undefined at 0\n",
+                EXIT_DOMAIN,
+            ),
+            (
+                "{\"id\": \"a\", \"expr\": \"x^3\"}\n{\"expr\": \"sin(x)\"}\n{\"id\": 7}\nnot json\n",
+                Options {
+                    jsonl: true,
+                    format: Format::Json,
+                    ..options()
+                },
+                "{\"id\":\"a\",\"derivative\":\"3 * x^2\"}\n\
+                 {\"id\":2,\"derivative\":\"cos(x)\"}\n\
+                 {\"id\":7,\"error\":{\"kind\":\"record\",\"message\":\"record has no string `expr` member\"}}\n\
+                 {\"id\":4,\"error\":{\"kind\":\"json\",\"message\":\"expected a value at column 1\"}}\n",
+                EXIT_PARSE,
+            ),
+            ("x\n", options(), "1\n", EXIT_SUCCESS),
+            (
+                "x^2\nx^-9223372036854775807\nsin(x)\n",
+                Options {
+                    order: 2,
+                    ..options()
+                },
+                "2\nerror: exponent -9223372036854775808 - 1 overflows\n-sin(x)\n",
+                EXIT_DIFF,
+            ),
+        ];
+
+        for case in cases {
+            let mut output = vec![];
+            let code = run(case.0.as_bytes(), &mut output, &case.1).unwrap();
+            assert_eq!(String::from_utf8(output).unwrap(), case.2);
+            assert_eq!(code, case.3);
+        }
+    }
+
+    // Fails every read, standing in for input that has not arrived yet.
+    struct Pending;
+
+    impl Read for Pending {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("pending"))
+        }
+    }
+
+    #[test]
+    fn test_run_streams() {
+        let input = BufReader::new("x^2\nsin(x)\n".as_bytes().chain(Pending));
+        let mut output = vec![];
+
+        assert!(run(input, &mut output, &options()).is_err());
+        assert_eq!(String::from_utf8(output).unwrap(), "2 * x\ncos(x)\n");
+    }
+
+    #[test]
+    fn test_run_jobs() {
+        let input: String = (1..=50)
+            .map(|n| format!("x^{} + sin({}x)\n", n, n))
+            .collect();
+        let mut sequential = vec![];
+        run(input.as_bytes(), &mut sequential, &options()).unwrap();
+        let mut parallel = vec![];
+        run(
+            input.as_bytes(),
+            &mut parallel,
+            &Options {
+                jobs: 4,
+                ..options()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(sequential.iter().filter(|&&b| b == b'\n').count(), 50);
+        assert_eq!(sequential, parallel);
+    }
+}
@@ -0,0 +1,353 @@
+//! Shorthand for building trees, e.g. `sin(x() * 2) + exp(x())`.
+//!
+//! The operators build the trees the parser does for the same chain: `a + b + c`
+//! is a single `Add`, `a - b` is `a + -1 * b`, and `a / b` is `a * b^-1`. They
+//! cannot see parentheses, though, so `(x() + 1) + 2` is also a single `Add`
+//! where the parser nests the group; `expr!` keeps groups apart.
+
+use std::ops;
+
+use crate::parse::negate;
+use crate::{Apply, Base, Cos, Custom, Exp, Expression, Log, Pow, Scaler, Sin, Tan, X};
+
+pub fn x() -> Expression {
+    Expression::Base(Base::X(X {}))
+}
+
+pub fn exp(arg: impl Into<Expression>) -> Expression {
+    Exp::new(arg.into()).into()
+}
+
+pub fn pow(arg: impl Into<Expression>, exp: isize) -> Expression {
+    Pow::new(exp, arg.into()).into()
+}
+
+pub fn log(arg: impl Into<Expression>) -> Expression {
+    Log::new(arg.into()).into()
+}
+
+pub fn sin(arg: impl Into<Expression>) -> Expression {
+    Sin::new(arg.into()).into()
+}
+
+pub fn cos(arg: impl Into<Expression>) -> Expression {
+    Cos::new(arg.into()).into()
+}
+
+pub fn tan(arg: impl Into<Expression>) -> Expression {
+    Tan::new(arg.into()).into()
+}
+
+/// A call to the undefined function `name`, such as `f(x)`.
+pub fn apply(name: &str, args: impl IntoIterator<Item = Expression>) -> Expression {
+    Apply::new(name, args.into_iter().collect()).into()
+}
+
+/// A call to the function registered as `name`.
+pub fn custom(name: &str, arg: impl Into<Expression>) -> Expression {
+    Custom::new(name, arg.into()).into()
+}
+
+impl From<Scaler> for Expression {
+    fn from(scaler: Scaler) -> Self {
+        Expression::Base(Base::Scaler(scaler))
+    }
+}
+
+impl<T: Into<Expression>> ops::Add<T> for Expression {
+    type Output = Expression;
+
+    fn add(self, rhs: T) -> Expression {
+        match self {
+            Expression::Add(mut terms) => {
+                terms.push(rhs.into());
+                Expression::Add(terms)
+            }
+            lhs => Expression::Add(vec![lhs, rhs.into()]),
+        }
+    }
+}
+
+impl<T: Into<Expression>> ops::Sub<T> for Expression {
+    type Output = Expression;
+
+    fn sub(self, rhs: T) -> Expression {
+        ops::Add::add(self, negate(rhs.into()))
+    }
+}
+
+impl<T: Into<Expression>> ops::Mul<T> for Expression {
+    type Output = Expression;
+
+    fn mul(self, rhs: T) -> Expression {
+        match self {
+            Expression::Mul(mut factors) => {
+                factors.push(rhs.into());
+                Expression::Mul(factors)
+            }
+            lhs => Expression::Mul(vec![lhs, rhs.into()]),
+        }
+    }
+}
+
+impl<T: Into<Expression>> ops::Div<T> for Expression {
+    type Output = Expression;
+
+    fn div(self, rhs: T) -> Expression {
+        ops::Mul::mul(self, pow(rhs, -1))
+    }
+}
+
+impl ops::Neg for Expression {
+    type Output = Expression;
+
+    fn neg(self) -> Expression {
+        negate(self)
+    }
+}
+
+/// An operand of `expr!`. Unlike a plain `Expression`, an `Add` or `Mul` in a
+/// group is not extended by the operator that follows it, just as the parser
+/// only extends the chain it is reading.
+#[doc(hidden)]
+pub struct Group(pub Expression);
+
+impl From<Group> for Expression {
+    fn from(group: Group) -> Self {
+        group.0
+    }
+}
+
+impl<T: Into<Expression>> ops::Add<T> for Group {
+    type Output = Expression;
+
+    fn add(self, rhs: T) -> Expression {
+        Expression::Add(vec![self.0, rhs.into()])
+    }
+}
+
+impl<T: Into<Expression>> ops::Sub<T> for Group {
+    type Output = Expression;
+
+    fn sub(self, rhs: T) -> Expression {
+        ops::Add::add(self, negate(rhs.into()))
+    }
+}
+
+impl<T: Into<Expression>> ops::Mul<T> for Group {
+    type Output = Expression;
+
+    fn mul(self, rhs: T) -> Expression {
+        Expression::Mul(vec![self.0, rhs.into()])
+    }
+}
+
+impl<T: Into<Expression>> ops::Div<T> for Group {
+    type Output = Expression;
+
+    fn div(self, rhs: T) -> Expression {
+        ops::Mul::mul(self, pow(rhs, -1))
+    }
+}
+
+impl ops::Neg for Group {
+    type Output = Group;
+
+    fn neg(self) -> Group {
+        Group(negate(self.0))
+    }
+}
+
+impl ops::Add<Expression> for Scaler {
+    type Output = Expression;
+
+    fn add(self, rhs: Expression) -> Expression {
+        Expression::from(self) + rhs
+    }
+}
+
+impl ops::Sub<Expression> for Scaler {
+    type Output = Expression;
+
+    fn sub(self, rhs: Expression) -> Expression {
+        Expression::from(self) - rhs
+    }
+}
+
+impl ops::Mul<Expression> for Scaler {
+    type Output = Expression;
+
+    fn mul(self, rhs: Expression) -> Expression {
+        Expression::from(self) * rhs
+    }
+}
+
+impl ops::Div<Expression> for Scaler {
+    type Output = Expression;
+
+    fn div(self, rhs: Expression) -> Expression {
+        Expression::from(self) / rhs
+    }
+}
+
+/// Builds an expression from mathematical notation at compile time, e.g.
+/// `expr!(sin(x^2) * exp(x) + 3)`. `^` takes an integer exponent, `ln` is
+/// accepted for `log`, and `{value}` inserts anything convertible into an
+/// `Expression`. The trees are the same as `parse` builds for the same text.
+#[macro_export]
+macro_rules! expr {
+    // `[...]` is the translated output so far and the second `[...]` the
+    // last operand, held back until it is known whether `^` follows.
+    (@munch [$($out:tt)*] [$($operand:tt)+] ^ - $exp:literal $($rest:tt)*) => {
+        $crate::expr!(@munch [$($out)*] [($crate::build::Group($crate::build::pow($($operand)+, -$exp)))] $($rest)*)
+    };
+    (@munch [$($out:tt)*] [$($operand:tt)+] ^ $exp:literal $($rest:tt)*) => {
+        $crate::expr!(@munch [$($out)*] [($crate::build::Group($crate::build::pow($($operand)+, $exp)))] $($rest)*)
+    };
+    (@munch [$($out:tt)*] [$($operand:tt)+] ^ ($($exp:tt)+) $($rest:tt)*) => {
+        $crate::expr!(@munch [$($out)*] [($crate::build::Group($crate::build::pow($($operand)+, ($($exp)+))))] $($rest)*)
+    };
+    (@munch [$($out:tt)*] [$($operand:tt)+] $($rest:tt)*) => {
+        $crate::expr!(@munch [$($out)* $($operand)+] [] $($rest)*)
+    };
+
+    // `+`, `-`, `*` and `/` keep their meaning and precedence in Rust. They
+    // come first so that `- 1` is not read as the literal `-1`.
+    (@munch [$($out:tt)*] [] + $($rest:tt)*) => {
+        $crate::expr!(@munch [$($out)* +] [] $($rest)*)
+    };
+    (@munch [$($out:tt)*] [] - $($rest:tt)*) => {
+        $crate::expr!(@munch [$($out)* -] [] $($rest)*)
+    };
+    (@munch [$($out:tt)*] [] * $($rest:tt)*) => {
+        $crate::expr!(@munch [$($out)* *] [] $($rest)*)
+    };
+    (@munch [$($out:tt)*] [] / $($rest:tt)*) => {
+        $crate::expr!(@munch [$($out)* /] [] $($rest)*)
+    };
+    (@munch [$($out:tt)*] [] ($($group:tt)+) $($rest:tt)*) => {
+        $crate::expr!(@munch [$($out)*] [($crate::build::Group($crate::expr!($($group)+)))] $($rest)*)
+    };
+    (@munch [$($out:tt)*] [] {$value:expr} $($rest:tt)*) => {
+        $crate::expr!(@munch [$($out)*] [($crate::build::Group($crate::Expression::from($value)))] $($rest)*)
+    };
+    (@munch [$($out:tt)*] [] x $($rest:tt)*) => {
+        $crate::expr!(@munch [$($out)*] [($crate::build::Group($crate::build::x()))] $($rest)*)
+    };
+    (@munch [$($out:tt)*] [] ln ($($arg:tt)+) $($rest:tt)*) => {
+        $crate::expr!(@munch [$($out)*] [($crate::build::Group($crate::build::log($crate::expr!($($arg)+))))] $($rest)*)
+    };
+    (@munch [$($out:tt)*] [] $function:ident ($($arg:tt)+) $($rest:tt)*) => {
+        $crate::expr!(@munch [$($out)*] [($crate::build::Group($crate::build::$function($crate::expr!($($arg)+))))] $($rest)*)
+    };
+    (@munch [$($out:tt)*] [] $scaler:literal $($rest:tt)*) => {
+        $crate::expr!(@munch [$($out)*] [($crate::build::Group($crate::Expression::from($scaler)))] $($rest)*)
+    };
+    (@munch [$($out:tt)*] []) => {
+        $crate::Expression::from($($out)*)
+    };
+
+    ($($tokens:tt)+) => {
+        $crate::expr!(@munch [] [] $($tokens)+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{cos, exp, log, pow, sin, tan, x};
+    use crate::{Base, Exp, Expression, Mul, Pow, Sin, X};
+
+    #[test]
+    fn test_build() {
+        let cases = [
+            (x(), Expression::Base(Base::X(X {}))),
+            (Expression::from(-2), Expression::Base(Base::Scaler(-2))),
+            (
+                sin(x() * 2) + exp(x()),
+                Expression::Add(vec![
+                    Expression::Base(Base::Sin(Sin {
+                        arg: Box::new(Expression::Mul(vec![
+                            Expression::Base(Base::X(X {})),
+                            Expression::Base(Base::Scaler(2)),
+                        ])),
+                    })),
+                    Expression::Base(Base::Exp(Exp {
+                        arg: Box::new(Expression::Base(Base::X(X {}))),
+                    })),
+                ]),
+            ),
+            (
+                2 / pow(x(), 3),
+                Expression::Mul(vec![
+                    Expression::Base(Base::Scaler(2)),
+                    Expression::Base(Base::Pow(Pow {
+                        exp: -1,
+                        arg: Box::new(Expression::Base(Base::Pow(Pow {
+                            exp: 3,
+                            arg: Box::new(Expression::Base(Base::X(X {}))),
+                        }))),
+                    })),
+                ]),
+            ),
+        ];
+
+        for case in cases {
+            assert_eq!(case.0, case.1);
+        }
+    }
+
+    #[test]
+    fn test_build_matches_parse() {
+        let cases = [
+            ("x + 1 - 2x", x() + 1 - 2 * x()),
+            ("x * cos(x) / tan(x)", x() * cos(x()) / tan(x())),
+            ("-sin(x)", -sin(x())),
+            ("1 - log(x)^-2", 1 - pow(log(x()), -2)),
+            ("-3 - x", -3 - x()),
+            ("(x + 1) * (x - 1)", (x() + 1) * (x() - 1)),
+            ("x * (x * x)", x() * (x() * x())),
+        ];
+
+        for case in cases {
+            assert_eq!(case.0.parse::<Expression>(), Ok(case.1), "{}", case.0);
+        }
+        // Only the parser sees parentheses around a left operand.
+        assert_eq!(
+            "(x + 1) + 2".parse::<Expression>(),
+            Ok(Expression::Add(vec![x() + 1, Expression::from(2)]))
+        );
+        assert_eq!(
+            (x() + 1) + 2,
+            Expression::Add(vec![x(), 1.into(), 2.into()])
+        );
+        let empty: Mul = vec![];
+        assert_eq!(Expression::Mul(empty) * x(), Expression::Mul(vec![x()]));
+    }
+
+    #[test]
+    fn test_expr_macro() {
+        let inner = expr!(x + 1);
+        let cases = [
+            ("sin(x^2) * exp(x) + 3", expr!(sin(x ^ 2) * exp(x) + 3)),
+            ("-x^2", expr!(-x ^ 2)),
+            ("x^-1 + (x + 1)^(-2)", expr!(x ^ -1 + (x + 1) ^ (-2))),
+            ("2 * x / ln(x)", expr!(2 * x / ln(x))),
+            ("tan(cos(x)) - 1", expr!(tan(cos(x)) - 1)),
+            ("x^10 * (x - (x - 1))", expr!(x ^ 10 * (x - (x - 1)))),
+            ("-3", expr!(-3)),
+            ("(x + 1) * x", expr!({ inner.clone() } * x)),
+            ("(x + 1) + 2", expr!((x + 1) + 2)),
+            ("(x * 2) * x", expr!((x * 2) * x)),
+            ("(x + 1) - x", expr!({ inner.clone() } - x)),
+            ("-(x * x) / (x - 1)", expr!(-(x * x) / (x - 1))),
+            ("2 * (x + 1)", expr!(2 * (x + 1))),
+            ("(x + 1)", expr!((x + 1))),
+            ("-x * 2", expr!(-x * 2)),
+            ("x * -2 - 3x", expr!(x * -2 - 3 * x)),
+        ];
+
+        for case in cases {
+            assert_eq!(case.0.parse::<Expression>(), Ok(case.1), "{}", case.0);
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use std::fmt;
+
+use crate::domain::Condition;
+use crate::function::lookup;
+use crate::{Base, Expression};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffError {
+    /// `Pow::diff` would compute `exp - 1` outside the range of `isize`.
+    Overflow { exp: isize },
+    /// Differentiating a `Derivative` would raise an order past `usize::MAX`.
+    OrderOverflow { order: usize },
+    /// A node that has no derivative rule.
+    Unsupported(String),
+    /// A call to a function that is not registered, so the expression is
+    /// undefined everywhere.
+    UnknownFunction(String),
+    /// The derivative would have more nodes than `DiffOptions::max_size`.
+    SizeLimit { size: usize, limit: usize },
+    /// A constant argument is outside the domain of its function, so the
+    /// expression is undefined everywhere.
+    InvalidDomain(Condition),
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Overflow { exp } => write!(f, "exponent {} - 1 overflows", exp),
+            Self::OrderOverflow { order } => write!(f, "derivative order {} + 1 overflows", order),
+            Self::Unsupported(node) => write!(f, "cannot differentiate {}", node),
+            Self::UnknownFunction(name) => write!(f, "unknown function `{}`", name),
+            Self::SizeLimit { size, limit } => write!(
+                f,
+                "derivative would have {} nodes, more than the limit of {}",
+                size, limit
+            ),
+            Self::InvalidDomain(condition) => write!(f, "`{}` does not hold", condition),
+        }
+    }
+}
+
+impl std::error::Error for DiffError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffOptions {
+    /// The largest derivative, counted in `Add`, `Mul` and `Base` nodes.
+    pub max_size: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            max_size: 1_000_000,
+        }
+    }
+}
+
+impl Expression {
+    pub fn try_diff(&self) -> Result<Expression, DiffError> {
+        self.try_diff_with(&DiffOptions::default())
+    }
+
+    /// Like `diff`, but checks the expression first instead of overflowing
+    /// or building a derivative of an expression that is undefined
+    /// everywhere.
+    pub fn try_diff_with(&self, options: &DiffOptions) -> Result<Expression, DiffError> {
+        check_overflow(self)?;
+        check_supported(self)?;
+        if let Some(condition) = self.domain_violations().into_iter().next() {
+            return Err(DiffError::InvalidDomain(condition));
+        }
+        let size = derivative_size(self);
+        if size > options.max_size {
+            return Err(DiffError::SizeLimit {
+                size,
+                limit: options.max_size,
+            });
+        }
+        Ok(self.diff())
+    }
+
+    /// The number of `Add`, `Mul` and `Base` nodes in the tree.
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Base(base) => base_children(base)
+                .into_iter()
+                .fold(1, |size, child| size.saturating_add(child.size())),
+            Self::Add(children) | Self::Mul(children) => children
+                .iter()
+                .fold(1, |size, child| size.saturating_add(child.size())),
+        }
+    }
+}
+
+fn base_children(base: &Base) -> Vec<&Expression> {
+    match base {
+        Base::Scaler(_) | Base::X(_) => vec![],
+        Base::Exp(exp) => vec![&exp.arg],
+        Base::Pow(pow) => vec![&pow.arg],
+        Base::Log(log) => vec![&log.arg],
+        Base::Sin(sin) => vec![&sin.arg],
+        Base::Cos(cos) => vec![&cos.arg],
+        Base::Tan(tan) => vec![&tan.arg],
+        Base::Custom(custom) => vec![&custom.arg],
+        Base::Apply(apply) => apply.args.iter().collect(),
+        Base::Derivative(derivative) => vec![&derivative.func],
+    }
+}
+
+fn check_overflow(expression: &Expression) -> Result<(), DiffError> {
+    match expression {
+        Expression::Base(Base::Pow(pow)) if pow.exp.checked_sub(1).is_none() => {
+            Err(DiffError::Overflow { exp: pow.exp })
+        }
+        Expression::Base(Base::Derivative(derivative)) => {
+            // Invalid derivatives are left to `check_supported`.
+            if derivative.check().is_ok() {
+                let orders = derivative.orders();
+                if let Some(&order) = orders.iter().find(|order| order.checked_add(1).is_none()) {
+                    return Err(DiffError::OrderOverflow { order });
+                }
+            }
+            check_overflow(&derivative.func)
+        }
+        Expression::Base(base) => base_children(base).into_iter().try_for_each(check_overflow),
+        Expression::Add(children) | Expression::Mul(children) => {
+            children.iter().try_for_each(check_overflow)
+        }
+    }
+}
+
+// Finds nodes that `diff` has no rule for.
+fn check_supported(expression: &Expression) -> Result<(), DiffError> {
+    match expression {
+        Expression::Base(Base::Custom(custom)) if lookup(&custom.name).is_none() => {
+            Err(DiffError::UnknownFunction(custom.name.clone()))
+        }
+        Expression::Base(Base::Derivative(derivative)) => {
+            derivative.check().map_err(DiffError::Unsupported)?;
+            check_supported(&derivative.func)
+        }
+        Expression::Base(base) => base_children(base)
+            .into_iter()
+            .try_for_each(check_supported),
+        Expression::Add(children) | Expression::Mul(children) => {
+            children.iter().try_for_each(check_supported)
+        }
+    }
+}
+
+// The size of `expression.diff()`, computed without building it. Each arm
+// mirrors the shape of the matching rule in `DiffBase`.
+fn derivative_size(expression: &Expression) -> usize {
+    let base = match expression {
+        Expression::Base(base) => base,
+        Expression::Add(add) => {
+            return add
+                .iter()
+                .fold(1, |size, term| size.saturating_add(derivative_size(term)))
+        }
+        Expression::Mul(mul) => {
+            let sizes: Vec<usize> = mul.iter().map(|factor| factor.size()).collect();
+            let total = sizes
+                .iter()
+                .fold(0, |total: usize, size| total.saturating_add(*size));
+            return mul
+                .iter()
+                .zip(&sizes)
+                .fold(1, |size, (factor, factor_size)| {
+                    size.saturating_add(1)
+                        .saturating_add(total - factor_size)
+                        .saturating_add(derivative_size(factor))
+                });
+        }
+    };
+    let args = match base {
+        Base::Apply(apply) => Some(&apply.args),
+        Base::Derivative(derivative) => derivative.apply().map(|apply| &apply.args),
+        _ => None,
+    };
+    if let Some(args) = args {
+        // A `Mul` of the partial derivative and `arg.diff()` for every
+        // argument, in an `Add` unless there is only one.
+        let terms = args.iter().enumerate().map(|(i, arg)| {
+            let partial = match base {
+                // the same node with the order raised
+                Base::Derivative(derivative) if derivative.wrt == i => expression.size(),
+                // a new `Derivative` around the node
+                _ => 1 + expression.size(),
+            };
+            (1 + partial).saturating_add(derivative_size(arg))
+        });
+        return match args.len() {
+            1 => terms.sum(),
+            _ => terms.fold(1, |size, term| size.saturating_add(term)),
+        };
+    }
+    let arg = match base_children(base)[..] {
+        [arg] => arg,
+        _ => return 1,
+    };
+    // The factors other than `arg.diff()` in the `Mul` built by the rule.
+    let outer = match base {
+        // exp(arg)
+        Base::Exp(_) => expression.size(),
+        // n, arg^(n - 1)
+        Base::Pow(_) => 1 + expression.size(),
+        // arg^-1; cos(arg)
+        Base::Log(_) | Base::Sin(_) => 1 + arg.size(),
+        // the registered derivative at arg
+        Base::Custom(custom) => custom.outer_derivative().size(),
+        // -1, sin(arg); cos(arg)^-2
+        _ => 2 + arg.size(),
+    };
+    (1 + outer).saturating_add(derivative_size(arg))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{apply, custom, pow, sin, x};
+    use crate::checked::{DiffError, DiffOptions};
+    use crate::function::register;
+    use crate::parse::parse;
+    use crate::{Derivative, Expression};
+
+    #[test]
+    fn test_try_diff() {
+        register("cube", |x| x.powi(3), 3 * pow(x(), 2)).unwrap();
+        let cases = [
+            "x",
+            "sin(x^2) * exp(x)",
+            "log(cos(x)) - tan(x) / x",
+            "(x + 1) * (x - 1) * x * tan(3)",
+            "log(2) * x^-3",
+            "cube(sin(x)) + x",
+        ];
+
+        for case in cases {
+            let expression = parse(case).unwrap();
+            let derivative = expression.try_diff().unwrap();
+            assert_eq!(derivative, expression.diff());
+            assert_eq!(
+                super::derivative_size(&expression),
+                derivative.size(),
+                "{}",
+                case
+            );
+        }
+    }
+
+    #[test]
+    fn test_try_diff_error() {
+        assert_eq!(
+            (sin(x()) * pow(x(), isize::MIN)).try_diff(),
+            Err(DiffError::Overflow { exp: isize::MIN })
+        );
+
+        let derivative =
+            Expression::from_sexpr("(derivative (apply f x) 18446744073709551615 0)").unwrap();
+        assert_eq!(
+            derivative.try_diff(),
+            Err(DiffError::OrderOverflow { order: usize::MAX })
+        );
+
+        let error = parse("x + log(1 - 1)").unwrap().try_diff().unwrap_err();
+        assert_eq!(error.to_string(), "`1 - 1 > 0` does not hold");
+
+        let error = (x() + custom("unregistered", x())).try_diff();
+        assert_eq!(
+            error,
+            Err(DiffError::UnknownFunction("unregistered".to_string()))
+        );
+
+        let cases = [
+            (
+                Derivative {
+                    func: Box::new(sin(x())),
+                    order: 1,
+                    wrt: 0,
+                },
+                "cannot differentiate the derivative of `sin(x)`, which is not a function",
+            ),
+            (
+                Derivative {
+                    func: Box::new(apply("f", [x()])),
+                    order: 1,
+                    wrt: 1,
+                },
+                "cannot differentiate the derivative of `f` with respect to argument 1, which it does not have",
+            ),
+        ];
+        for case in cases {
+            let error = (x() * Expression::from(case.0)).try_diff().unwrap_err();
+            assert_eq!(error.to_string(), case.1);
+        }
+
+        let product = parse("x * x * x * x").unwrap();
+        let options = DiffOptions { max_size: 20 };
+        assert_eq!(
+            product.try_diff_with(&options),
+            Err(DiffError::SizeLimit {
+                size: 21,
+                limit: 20
+            })
+        );
+        assert!(product.try_diff_with(&DiffOptions { max_size: 21 }).is_ok());
+    }
+}
@@ -0,0 +1,407 @@
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+
+use crate::batch;
+use diff_rs::checked::DiffError;
+use diff_rs::json::Value;
+use diff_rs::latex::derivative_equation;
+use diff_rs::parse::parse_with_variable;
+use diff_rs::Expression;
+
+pub const EXIT_SUCCESS: i32 = 0;
+pub const EXIT_USAGE: i32 = 1;
+pub const EXIT_PARSE: i32 = 2;
+pub const EXIT_DOMAIN: i32 = 3;
+pub const EXIT_DIFF: i32 = 4;
+
+const USAGE: &str = "\
+usage: diff_rs [options] <expression>
+       diff_rs [options] --batch <file|->
+       diff_rs                 start the interactive prompt
+
+options:
+  --format <text|json|latex|sexpr>
+                        output format (default: text)
+  --wrt <name>          name of the variable (default: x)
+  --order <n>           differentiate n times (default: 1)
+  --simplify            simplify the result
+  --eval <name>=<value> also evaluate the derivative at a point
+  --batch <file|->      differentiate every line of a file or stdin
+  --jsonl               batch lines are {\"id\": ..., \"expr\": ...} records
+  --jobs <n>            worker threads for batch mode (default: 1)
+  --help                show this message
+
+exit codes: 0 success, 1 usage, 2 parse error, 3 domain error, 4 the
+derivative cannot be computed; in batch mode the highest code of any line";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    Latex,
+    Sexpr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub expression: Option<String>,
+    pub format: Format,
+    pub variable: String,
+    pub order: usize,
+    pub simplify: bool,
+    pub eval: Option<f64>,
+    pub batch: Option<String>,
+    pub jsonl: bool,
+    pub jobs: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    pub kind: &'static str,
+    pub message: String,
+    pub code: i32,
+}
+
+impl Failure {
+    pub fn to_json(&self) -> Value {
+        Value::object([
+            ("kind", self.kind.into()),
+            ("message", self.message.as_str().into()),
+        ])
+    }
+}
+
+pub struct Success {
+    pub derivative: String,
+    pub value: Option<f64>,
+}
+
+/// Runs the command line described by `args` (without the program name)
+/// and returns the process exit code.
+pub fn run(args: &[String], stdout: &mut impl Write, stderr: &mut impl Write) -> i32 {
+    let options = match parse_args(args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            let _ = writeln!(stdout, "{}", USAGE);
+            return EXIT_SUCCESS;
+        }
+        Err(message) => {
+            let _ = writeln!(stderr, "error: {}\n\n{}", message, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+
+    if let Some(path) = &options.batch {
+        let result = if path == "-" {
+            batch::run(io::stdin().lock(), stdout, &options)
+        } else {
+            File::open(path).and_then(|file| batch::run(BufReader::new(file), stdout, &options))
+        };
+        return match result {
+            Ok(code) => code,
+            Err(error) => {
+                let _ = writeln!(stderr, "error: {}: {}", path, error);
+                EXIT_USAGE
+            }
+        };
+    }
+
+    let source = options.expression.as_deref().unwrap_or_default();
+    let _ = match (process(source, &options), options.format) {
+        (Ok(success), Format::Text | Format::Latex | Format::Sexpr) => match success.value {
+            Some(value) => writeln!(stdout, "{}\n{}", success.derivative, value),
+            None => writeln!(stdout, "{}", success.derivative),
+        },
+        (Ok(success), Format::Json) => {
+            let mut record = vec![
+                ("input".to_string(), Value::from(source)),
+                (
+                    "variable".to_string(),
+                    Value::from(options.variable.as_str()),
+                ),
+                ("order".to_string(), Value::Int(options.order as i64)),
+                ("derivative".to_string(), Value::from(success.derivative)),
+            ];
+            if let Some(value) = success.value {
+                record.push(("value".to_string(), Value::from(value)));
+            }
+            writeln!(stdout, "{}", Value::Object(record))
+        }
+        (Err(failure), Format::Text | Format::Latex | Format::Sexpr) => {
+            let _ = writeln!(stderr, "error: {}", failure.message);
+            return failure.code;
+        }
+        (Err(failure), Format::Json) => {
+            let _ = writeln!(stdout, "{}", Value::object([("error", failure.to_json())]));
+            return failure.code;
+        }
+    };
+    EXIT_SUCCESS
+}
+
+/// Parses and differentiates a single expression as described by `options`.
+pub fn process(source: &str, options: &Options) -> Result<Success, Failure> {
+    let expression = parse_with_variable(source, &options.variable).map_err(|error| Failure {
+        kind: "parse",
+        message: error.to_string(),
+        code: EXIT_PARSE,
+    })?;
+    let derivative =
+        derivative(&expression, options.order, options.simplify).map_err(|error| Failure {
+            kind: "diff",
+            message: error.to_string(),
+            code: EXIT_DIFF,
+        })?;
+    let value = match options.eval {
+        Some(x) => Some(derivative.compile().call(x).map_err(|error| Failure {
+            kind: "domain",
+            message: error.to_string(),
+            code: EXIT_DOMAIN,
+        })?),
+        None => None,
+    };
+    let text = match options.format {
+        Format::Latex => {
+            derivative_equation(&expression, &derivative, options.order, &options.variable)
+        }
+        Format::Sexpr => derivative.to_sexpr(),
+        _ => derivative
+            .display_with_variable(&options.variable)
+            .to_string(),
+    };
+    Ok(Success {
+        derivative: text,
+        value,
+    })
+}
+
+fn derivative(
+    expression: &Expression,
+    order: usize,
+    simplify: bool,
+) -> Result<Expression, DiffError> {
+    let mut derivative = expression.clone();
+    for _ in 0..order {
+        derivative = derivative.try_diff()?;
+        if simplify {
+            derivative = derivative.simplify();
+        }
+    }
+    Ok(derivative)
+}
+
+// Returns `Ok(None)` when help was requested.
+fn parse_args(args: &[String]) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        expression: None,
+        format: Format::Text,
+        variable: "x".to_string(),
+        order: 1,
+        simplify: false,
+        eval: None,
+        batch: None,
+        jsonl: false,
+        jobs: 1,
+    };
+    let mut eval = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next().cloned())
+                .ok_or_else(|| format!("`{}` needs a value", flag))
+        };
+        match flag {
+            "-h" | "--help" => return Ok(None),
+            "--format" => {
+                options.format = match value()?.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    "latex" => Format::Latex,
+                    "sexpr" => Format::Sexpr,
+                    other => return Err(format!("unknown format `{}`", other)),
+                }
+            }
+            "--wrt" => {
+                let variable = value()?;
+                let mut chars = variable.chars();
+                let valid = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+                    && chars.all(|c| c.is_alphanumeric() || c == '_');
+                if !valid {
+                    return Err(format!("`{}` is not a valid variable name", variable));
+                }
+                options.variable = variable;
+            }
+            "--order" => {
+                let v = value()?;
+                options.order = v
+                    .parse()
+                    .map_err(|_| format!("`{}` is not a valid order", v))?;
+            }
+            "--simplify" => options.simplify = true,
+            "--eval" => eval = Some(value()?),
+            "--batch" => options.batch = Some(value()?),
+            "--jsonl" => options.jsonl = true,
+            "--jobs" => {
+                let v = value()?;
+                options.jobs = match v.parse() {
+                    Ok(jobs) if jobs > 0 => jobs,
+                    _ => return Err(format!("`{}` is not a valid number of jobs", v)),
+                };
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option `{}`", flag)),
+            _ if options.expression.is_some() => {
+                return Err("expected a single expression".to_string())
+            }
+            _ => options.expression = Some(arg.clone()),
+        }
+    }
+
+    if let Some(eval) = eval {
+        let point = match eval.split_once('=') {
+            Some((name, point)) if name == options.variable => point,
+            Some((name, _)) => return Err(format!("unknown variable `{}`", name)),
+            None => &eval,
+        };
+        options.eval = Some(
+            point
+                .parse::<f64>()
+                .map_err(|_| format!("`{}` is not a number", point))?,
+        );
+    }
+    match (&options.expression, &options.batch) {
+        (None, None) => return Err("missing expression".to_string()),
+        (Some(_), Some(_)) => return Err("`--batch` does not take an expression".to_string()),
+        _ => {}
+    }
+    Ok(Some(options))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::{run, EXIT_DIFF, EXIT_DOMAIN, EXIT_PARSE, EXIT_SUCCESS, EXIT_USAGE};
+
+    #[test]
+    fn test_run() {
+        let cases = [
+            (vec!["sin(x^2)"], EXIT_SUCCESS, "cos(x^2) * 2 * x^1 * 1\n", ""),
+            (
+                vec!["--simplify", "sin(x^2)"],
+                EXIT_SUCCESS,
+                "2 * x * cos(x^2)\n",
+                "",
+            ),
+            (
+                vec!["--wrt", "t", "--order=2", "--simplify", "t^3"],
+                EXIT_SUCCESS,
+                "6 * t\n",
+                "",
+            ),
+            (
+                vec!["--simplify", "--eval", "x=1.5", "x^2"],
+                EXIT_SUCCESS,
+                "2 * x\n3\n",
+                "",
+            ),
+            (
+                vec!["--format", "json", "--simplify", "--eval", "2", "x^3"],
+                EXIT_SUCCESS,
+                "{\"input\":\"x^3\",\"variable\":\"x\",\"order\":1,\"derivative\":\"3 * x^2\",\"value\":12.0}\n",
+                "",
+            ),
+            (
+                vec!["--eval", "t=1", "--wrt", "t", "t^2"],
+                EXIT_SUCCESS,
+                "2 * t^1 * 1\n2\n",
+                "",
+            ),
+            (
+                vec!["--format", "latex", "--simplify", "exp(x) / x"],
+                EXIT_SUCCESS,
+                "\\frac{d}{dx} \\left(\\frac{e^{x}}{x}\\right) = -\\frac{e^{x}}{x^{2}} + \\frac{e^{x}}{x}\n",
+                "",
+            ),
+            (
+                vec!["--format=sexpr", "exp(x)"],
+                EXIT_SUCCESS,
+                "(mul (exp x) 1)\n",
+                "",
+            ),
+            (
+                vec!["sin(x"],
+                EXIT_PARSE,
+                "",
+                "error: unclosed `(` at column 4\n",
+            ),
+            (
+                vec!["--format", "json", "sin(x"],
+                EXIT_PARSE,
+                "{\"error\":{\"kind\":\"parse\",\"message\":\"unclosed `(` at column 4\"}}\n",
+                "",
+            ),
+            (
+                vec!["--eval", "x=0", "log(x)"],
+                EXIT_DOMAIN,
+                "",
+                "error: `pow` is undefined at 0\n",
+            ),
+            (
+                vec!["--order", "2", "x^-9223372036854775807"],
+                EXIT_DIFF,
+                "",
+                "error: exponent -9223372036854775808 - 1 overflows\n",
+            ),
+            (
+                vec!["--format=json", "--order=2", "x^-9223372036854775807"],
+                EXIT_DIFF,
+                "{\"error\":{\"kind\":\"diff\",\"message\":\"exponent -9223372036854775808 - 1 overflows\"}}\n",
+                "",
+            ),
+        ];
+
+        for case in cases {
+            let args: Vec<String> = case.0.iter().map(|s| s.to_string()).collect();
+            let mut stdout = vec![];
+            let mut stderr = vec![];
+            assert_eq!(run(&args, &mut stdout, &mut stderr), case.1, "{:?}", args);
+            assert_eq!(String::from_utf8(stdout).unwrap(), case.2);
+            assert_eq!(String::from_utf8(stderr).unwrap(), case.3);
+        }
+    }
+
+    #[test]
+    fn test_run_usage() {
+        let cases = [
+            vec![],
+            vec!["x", "x"],
+            vec!["--order", "two", "x"],
+            vec!["--format", "yaml", "x"],
+            vec!["--eval", "y=1", "x"],
+            vec!["--wrt", "1x", "x"],
+            vec!["--frobnicate", "x"],
+            vec!["x", "--order"],
+            vec!["--batch", "-", "x"],
+            vec!["--jobs", "0", "--batch", "-"],
+        ];
+
+        for case in cases {
+            let args: Vec<String> = case.iter().map(|s| s.to_string()).collect();
+            let mut stdout = vec![];
+            let mut stderr = vec![];
+            assert_eq!(
+                run(&args, &mut stdout, &mut stderr),
+                EXIT_USAGE,
+                "{:?}",
+                args
+            );
+            assert!(stdout.is_empty());
+            assert!(String::from_utf8(stderr).unwrap().starts_with("error: "));
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::fmt;
+
+use crate::function::lookup;
+use crate::{Base, Expression};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DomainError {
+    pub function: &'static str,
+    pub arg: f64,
+}
+
+impl fmt::Display for DomainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` is undefined at {}", self.function, self.arg)
+    }
+}
+
+impl std::error::Error for DomainError {}
+
+// `cos` of the nearest `f64` to `pi/2` is about `6e-17`.
+const POLE_TOLERANCE: f64 = 1e-12;
+
+type Function = Box<dyn Fn(f64) -> Result<f64, DomainError>>;
+
+pub struct Compiled(Function);
+
+impl Compiled {
+    pub fn call(&self, x: f64) -> Result<f64, DomainError> {
+        (self.0)(x)
+    }
+}
+
+impl Expression {
+    /// Turns the tree into a closure once so it can be evaluated at many
+    /// points without walking the tree again. Unlike `eval`, points outside
+    /// the domain of `Log`, `Tan` or a negative `Pow` are reported as errors
+    /// instead of producing `NaN` or infinities.
+    pub fn compile(&self) -> Compiled {
+        Compiled(compile(self))
+    }
+}
+
+fn compile(expression: &Expression) -> Function {
+    match expression {
+        Expression::Base(base) => compile_base(base),
+        Expression::Add(add) => {
+            let terms: Vec<Function> = add.iter().map(compile).collect();
+            Box::new(move |x| terms.iter().map(|term| term(x)).sum())
+        }
+        Expression::Mul(mul) => {
+            let factors: Vec<Function> = mul.iter().map(compile).collect();
+            Box::new(move |x| factors.iter().map(|factor| factor(x)).product())
+        }
+    }
+}
+
+fn compile_base(base: &Base) -> Function {
+    match base {
+        Base::Scaler(scaler) => {
+            let scaler = *scaler as f64;
+            Box::new(move |_| Ok(scaler))
+        }
+        Base::X(_) => Box::new(Ok),
+        Base::Exp(exp) => {
+            let arg = compile(&exp.arg);
+            Box::new(move |x| Ok(arg(x)?.exp()))
+        }
+        Base::Pow(pow) => {
+            let arg = compile(&pow.arg);
+            let exp = pow.exp;
+            Box::new(move |x| {
+                let arg = arg(x)?;
+                if exp < 0 && arg == 0.0 {
+                    return Err(DomainError {
+                        function: "pow",
+                        arg,
+                    });
+                }
+                Ok(arg.powf(exp as f64))
+            })
+        }
+        Base::Log(log) => {
+            let arg = compile(&log.arg);
+            Box::new(move |x| {
+                let arg = arg(x)?;
+                if arg <= 0.0 {
+                    return Err(DomainError {
+                        function: "log",
+                        arg,
+                    });
+                }
+                Ok(arg.ln())
+            })
+        }
+        Base::Sin(sin) => {
+            let arg = compile(&sin.arg);
+            Box::new(move |x| Ok(arg(x)?.sin()))
+        }
+        Base::Cos(cos) => {
+            let arg = compile(&cos.arg);
+            Box::new(move |x| Ok(arg(x)?.cos()))
+        }
+        Base::Tan(tan) => {
+            let arg = compile(&tan.arg);
+            Box::new(move |x| {
+                let arg = arg(x)?;
+                // The poles `pi/2 + k*pi` are not representable, so treat
+                // points within rounding distance of one as the pole.
+                if arg.cos().abs() < POLE_TOLERANCE {
+                    return Err(DomainError {
+                        function: "tan",
+                        arg,
+                    });
+                }
+                Ok(arg.tan())
+            })
+        }
+        // Undefined functions have no value, as in `eval`.
+        Base::Apply(_) | Base::Derivative(_) => Box::new(|_| Ok(f64::NAN)),
+        // A `NaN` from the registered function marks a point outside its
+        // domain. Unregistered functions have no value, as in `eval`.
+        Base::Custom(custom) => {
+            let arg = compile(&custom.arg);
+            let function = match lookup(&custom.name) {
+                Some(function) => function,
+                None => return Box::new(|_| Ok(f64::NAN)),
+            };
+            Box::new(move |x| {
+                let arg = arg(x)?;
+                let value = function.eval(arg);
+                if value.is_nan() && !arg.is_nan() {
+                    return Err(DomainError {
+                        function: function.name,
+                        arg,
+                    });
+                }
+                Ok(value)
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use crate::build::{custom, log, pow, sin, tan, x};
+    use crate::compile::DomainError;
+
+    #[test]
+    fn test_compile() {
+        let cases = [
+            (sin(x()) + 3 * pow(x(), 2), 2.0, Ok(2.0_f64.sin() + 12.0)),
+            (
+                log(x()),
+                -1.0,
+                Err(DomainError {
+                    function: "log",
+                    arg: -1.0,
+                }),
+            ),
+            (
+                pow(x(), -1),
+                0.0,
+                Err(DomainError {
+                    function: "pow",
+                    arg: 0.0,
+                }),
+            ),
+            (
+                tan(x()),
+                FRAC_PI_2,
+                Err(DomainError {
+                    function: "tan",
+                    arg: FRAC_PI_2,
+                }),
+            ),
+            (tan(x()), 1.0, Ok(1.0_f64.tan())),
+        ];
+
+        for case in cases {
+            assert_eq!(case.0.compile().call(case.1), case.2);
+            if let Ok(value) = case.2 {
+                assert_eq!(case.0.eval(case.1), value);
+            }
+        }
+        let unregistered = custom("unregistered", x()).compile().call(1.0);
+        assert!(unregistered.unwrap().is_nan());
+    }
+}
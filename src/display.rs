@@ -0,0 +1,314 @@
+use std::fmt;
+
+use crate::{Base, Expression, Pow, Scaler};
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Printer { variable: "x" }.expression(self, f)
+    }
+}
+
+impl fmt::Display for Base {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Printer { variable: "x" }.base(self, f)
+    }
+}
+
+impl Expression {
+    /// Displays the expression with the variable printed as `variable`
+    /// instead of `x`.
+    pub fn display_with_variable<'a>(&'a self, variable: &'a str) -> impl fmt::Display + 'a {
+        WithVariable {
+            expression: self,
+            variable,
+        }
+    }
+}
+
+struct WithVariable<'a> {
+    expression: &'a Expression,
+    variable: &'a str,
+}
+
+impl fmt::Display for WithVariable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Printer {
+            variable: self.variable,
+        }
+        .expression(self.expression, f)
+    }
+}
+
+struct Printer<'a> {
+    variable: &'a str,
+}
+
+impl Printer<'_> {
+    fn expression(&self, expression: &Expression, f: &mut fmt::Formatter) -> fmt::Result {
+        match expression {
+            Expression::Base(base) => self.base(base, f),
+            Expression::Add(add) => self.add(add, f),
+            Expression::Mul(mul) => self.mul(mul, f),
+        }
+    }
+
+    fn base(&self, base: &Base, f: &mut fmt::Formatter) -> fmt::Result {
+        match base {
+            Base::Scaler(scaler) => write!(f, "{}", scaler),
+            Base::X(_) => write!(f, "{}", self.variable),
+            Base::Exp(exp) => self.function("exp", &exp.arg, f),
+            Base::Pow(pow) => {
+                self.pow_base(&pow.arg, f)?;
+                write!(f, "^{}", pow.exp)
+            }
+            Base::Log(log) => self.function("log", &log.arg, f),
+            Base::Sin(sin) => self.function("sin", &sin.arg, f),
+            Base::Cos(cos) => self.function("cos", &cos.arg, f),
+            Base::Tan(tan) => self.function("tan", &tan.arg, f),
+            Base::Custom(custom) => self.function(&custom.name, &custom.arg, f),
+            Base::Apply(apply) => self.call(&apply.name, &apply.args, f),
+            Base::Derivative(derivative) => match derivative.apply() {
+                Some(apply) => {
+                    let name = match derivative.orders()[..] {
+                        [order] if order <= 3 => format!("{}{}", apply.name, "'".repeat(order)),
+                        [order] => format!("{}^({})", apply.name, order),
+                        ref orders => format!(
+                            "{}^({})",
+                            apply.name,
+                            orders
+                                .iter()
+                                .map(|order| order.to_string())
+                                .collect::<Vec<_>>()
+                                .join(",")
+                        ),
+                    };
+                    self.call(&name, &apply.args, f)
+                }
+                None => {
+                    write!(f, "derivative(")?;
+                    self.expression(&derivative.func, f)?;
+                    write!(f, ", {}, {})", derivative.order, derivative.wrt)
+                }
+            },
+        }
+    }
+
+    fn call(&self, name: &str, args: &[Expression], f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}(", name)?;
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            self.expression(arg, f)?;
+        }
+        write!(f, ")")
+    }
+
+    fn function(&self, name: &str, arg: &Expression, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}(", name)?;
+        self.expression(arg, f)?;
+        write!(f, ")")
+    }
+
+    fn pow_base(&self, arg: &Expression, f: &mut fmt::Formatter) -> fmt::Result {
+        match arg {
+            Expression::Base(Base::Scaler(scaler)) if *scaler >= 0 => write!(f, "{}", scaler),
+            Expression::Base(Base::Scaler(_) | Base::Pow(_)) | Expression::Add(_) => {
+                self.parenthesized(arg, f)
+            }
+            Expression::Mul(mul) if mul.len() != 1 => self.parenthesized(arg, f),
+            _ => self.expression(arg, f),
+        }
+    }
+
+    fn parenthesized(&self, expression: &Expression, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(")?;
+        self.expression(expression, f)?;
+        write!(f, ")")
+    }
+
+    fn add(&self, add: &[Expression], f: &mut fmt::Formatter) -> fmt::Result {
+        if add.is_empty() {
+            return write!(f, "0");
+        }
+        for (i, term) in add.iter().enumerate() {
+            if i == 0 {
+                self.expression(term, f)?;
+            } else if let Some(negated) = negated(term) {
+                write!(f, " - ")?;
+                self.term(&negated, true, f)?;
+            } else {
+                write!(f, " + ")?;
+                self.term(term, false, f)?;
+            }
+        }
+        Ok(())
+    }
+
+    // A term after a binary `+` or, if `subtracted`, `-`.
+    fn term(&self, term: &Expression, subtracted: bool, f: &mut fmt::Formatter) -> fmt::Result {
+        match term {
+            Expression::Add(add) if add.len() > 1 && (subtracted || negated(&add[0]).is_some()) => {
+                self.parenthesized(term, f)
+            }
+            _ => self.expression(term, f),
+        }
+    }
+
+    fn mul(&self, mul: &[Expression], f: &mut fmt::Formatter) -> fmt::Result {
+        let mut numerator = vec![];
+        let mut denominator = vec![];
+        for factor in mul {
+            match factor {
+                Expression::Base(Base::Pow(pow)) if pow.exp < 0 && pow.exp != isize::MIN => {
+                    denominator.push(if pow.exp == -1 {
+                        *pow.arg.clone()
+                    } else {
+                        Expression::Base(Base::Pow(Pow {
+                            exp: -pow.exp,
+                            arg: pow.arg.clone(),
+                        }))
+                    })
+                }
+                factor => numerator.push(factor),
+            }
+        }
+
+        match numerator.as_slice() {
+            [] => write!(f, "1")?,
+            [Expression::Base(Base::Scaler(-1)), rest @ ..] if !rest.is_empty() => {
+                write!(f, "-")?;
+                self.factors(rest, f)?;
+            }
+            factors => self.factors(factors, f)?,
+        }
+        match denominator.as_slice() {
+            [] => Ok(()),
+            [factor] => {
+                write!(f, " / ")?;
+                match factor {
+                    Expression::Base(Base::Scaler(scaler)) if *scaler < 0 => {
+                        self.parenthesized(factor, f)
+                    }
+                    Expression::Base(_) => self.expression(factor, f),
+                    _ => self.parenthesized(factor, f),
+                }
+            }
+            _ => {
+                write!(f, " / (")?;
+                self.factors(&denominator.iter().collect::<Vec<_>>(), f)?;
+                write!(f, ")")
+            }
+        }
+    }
+
+    fn factors(&self, factors: &[&Expression], f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, factor) in factors.iter().enumerate() {
+            if i > 0 {
+                write!(f, " * ")?;
+            }
+            match factor {
+                Expression::Add(add) if add.len() != 1 => self.parenthesized(factor, f)?,
+                _ if i > 0 && negated(factor).is_some() => self.parenthesized(factor, f)?,
+                _ => self.expression(factor, f)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Returns `-term` when `term` is printed with a leading minus sign, so that
+// sums can be written as `a - b` instead of `a + -b`.
+pub(crate) fn negated(term: &Expression) -> Option<Expression> {
+    match term {
+        Expression::Base(Base::Scaler(scaler)) if *scaler < 0 => scaler
+            .checked_neg()
+            .map(|s| Expression::Base(Base::Scaler(s))),
+        Expression::Mul(mul) => match mul.first() {
+            Some(Expression::Base(Base::Scaler(scaler))) if *scaler < 0 => {
+                let positive: Scaler = scaler.checked_neg()?;
+                let mut rest = mul[1..].to_vec();
+                if positive != 1 || rest.is_empty() {
+                    rest.insert(0, Expression::Base(Base::Scaler(positive)));
+                }
+                Some(if rest.len() == 1 {
+                    rest.remove(0)
+                } else {
+                    Expression::Mul(rest)
+                })
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{apply, pow, sin, x};
+    use crate::parse::parse;
+    use crate::{Derivative, Expression};
+
+    #[test]
+    fn test_display() {
+        let cases = [
+            ("x", "x"),
+            ("-3", "-3"),
+            ("sin(x^2) + exp(x) - 1", "sin(x^2) + exp(x) - 1"),
+            ("x - 2x", "x - 2 * x"),
+            ("-x * cos(x)", "-x * cos(x)"),
+            ("x * (x + 1)", "x * (x + 1)"),
+            ("(x + 1)^2", "(x + 1)^2"),
+            ("(-2)^3", "(-2)^3"),
+            ("(x^2)^3", "(x^2)^3"),
+            ("x^-2", "x^-2"),
+            ("1 / x", "1 / x"),
+            ("sin(x) / (x + 1)", "sin(x) / (x + 1)"),
+            ("x / x^2 / tan(x)", "x / (x^2 * tan(x))"),
+            ("x * -2", "x * (-2)"),
+            ("1 / -2", "1 / (-2)"),
+            ("x - (x + 1)", "x - (x + 1)"),
+            ("x - (1 - x)", "x - (1 - x)"),
+        ];
+
+        for case in cases {
+            let expression = parse(case.0).unwrap();
+            assert_eq!(expression.to_string(), case.1);
+            assert_eq!(parse(case.1).unwrap().to_string(), case.1);
+        }
+    }
+
+    #[test]
+    fn test_display_undefined() {
+        let f = apply("f", [x()]);
+        let g = apply("g", [x(), pow(x(), 2)]);
+        let cases = [
+            (sin(f.clone()).diff(), "cos(f(x)) * f'(x) * 1"),
+            (Derivative::new(f.clone(), 3, 0).unwrap().into(), "f'''(x)"),
+            (Derivative::new(f, 4, 0).unwrap().into(), "f^(4)(x)"),
+            (g.clone(), "g(x, x^2)"),
+            (
+                Derivative::new(Derivative::new(g, 2, 0).unwrap().into(), 1, 1)
+                    .unwrap()
+                    .into(),
+                "g^(2,1)(x, x^2)",
+            ),
+            (apply("c", []), "c()"),
+            (
+                // Only a `Fold` can build this, as `Derivative::new` rejects it.
+                Derivative {
+                    func: Box::new(sin(x())),
+                    order: 1,
+                    wrt: 0,
+                }
+                .into(),
+                "derivative(sin(x), 1, 0)",
+            ),
+        ];
+
+        for case in cases {
+            let expression: Expression = case.0;
+            assert_eq!(expression.to_string(), case.1);
+        }
+    }
+}
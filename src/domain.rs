@@ -0,0 +1,233 @@
+use std::fmt;
+
+use crate::{Base, Cos, Expression};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Constraint {
+    /// `expression > 0`, required by `Log`.
+    Positive,
+    /// `expression != 0`, required by a negative `Pow` and, through
+    /// `cos(arg)`, by `Tan`.
+    NonZero,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The expression depends on `x`, so the condition restricts the domain.
+    Unknown,
+    /// The expression is constant and satisfies the condition.
+    Holds,
+    /// The expression is constant and breaks the condition, so the whole
+    /// expression is undefined everywhere.
+    Violated,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub expression: Expression,
+    pub constraint: Constraint,
+    pub status: Status,
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.constraint {
+            Constraint::Positive => write!(f, "{} > 0", self.expression),
+            Constraint::NonZero => write!(f, "{} != 0", self.expression),
+        }
+    }
+}
+
+impl Expression {
+    /// Collects the conditions under which the expression is defined, outer
+    /// functions first and without duplicates.
+    pub fn domain_conditions(&self) -> Vec<Condition> {
+        let mut conditions = vec![];
+        collect(self, &mut conditions);
+        conditions
+    }
+
+    /// The conditions among `domain_conditions` that constant arguments break,
+    /// such as `0 > 0` for `log(0)`.
+    pub fn domain_violations(&self) -> Vec<Condition> {
+        self.domain_conditions()
+            .into_iter()
+            .filter(|condition| condition.status == Status::Violated)
+            .collect()
+    }
+}
+
+fn collect(expression: &Expression, conditions: &mut Vec<Condition>) {
+    let base = match expression {
+        Expression::Base(base) => base,
+        Expression::Add(children) | Expression::Mul(children) => {
+            for child in children {
+                collect(child, conditions);
+            }
+            return;
+        }
+    };
+    let (condition, arg) = match base {
+        Base::Scaler(_) | Base::X(_) => return,
+        Base::Exp(exp) => (None, &exp.arg),
+        Base::Pow(pow) if pow.exp < 0 => (Some((*pow.arg.clone(), Constraint::NonZero)), &pow.arg),
+        Base::Pow(pow) => (None, &pow.arg),
+        Base::Log(log) => (Some((*log.arg.clone(), Constraint::Positive)), &log.arg),
+        Base::Sin(sin) => (None, &sin.arg),
+        Base::Cos(cos) => (None, &cos.arg),
+        Base::Custom(custom) => (None, &custom.arg),
+        // Nothing is known about the domain of an undefined function.
+        Base::Apply(apply) => {
+            for arg in &apply.args {
+                collect(arg, conditions);
+            }
+            return;
+        }
+        Base::Derivative(derivative) => (None, &derivative.func),
+        Base::Tan(tan) => (
+            Some((
+                Expression::Base(Base::Cos(Cos {
+                    arg: tan.arg.clone(),
+                })),
+                Constraint::NonZero,
+            )),
+            &tan.arg,
+        ),
+    };
+    if let Some((expression, constraint)) = condition {
+        let duplicate = conditions
+            .iter()
+            .any(|c| c.expression == expression && c.constraint == constraint);
+        if !duplicate {
+            let status = status(&expression, constraint);
+            conditions.push(Condition {
+                expression,
+                constraint,
+                status,
+            });
+        }
+    }
+    collect(arg, conditions);
+}
+
+fn status(expression: &Expression, constraint: Constraint) -> Status {
+    if depends_on_x(expression) {
+        return Status::Unknown;
+    }
+    // An argument that cannot be evaluated breaks a condition of its own,
+    // which is reported separately.
+    let value = match expression.compile().call(0.0) {
+        Ok(value) => value,
+        Err(_) => return Status::Unknown,
+    };
+    let holds = match constraint {
+        Constraint::Positive => value > 0.0,
+        Constraint::NonZero => value != 0.0,
+    };
+    if holds {
+        Status::Holds
+    } else {
+        Status::Violated
+    }
+}
+
+fn depends_on_x(expression: &Expression) -> bool {
+    match expression {
+        Expression::Base(base) => match base {
+            Base::Scaler(_) => false,
+            Base::X(_) => true,
+            Base::Exp(exp) => depends_on_x(&exp.arg),
+            Base::Pow(pow) => depends_on_x(&pow.arg),
+            Base::Log(log) => depends_on_x(&log.arg),
+            Base::Sin(sin) => depends_on_x(&sin.arg),
+            Base::Cos(cos) => depends_on_x(&cos.arg),
+            Base::Tan(tan) => depends_on_x(&tan.arg),
+            Base::Custom(custom) => depends_on_x(&custom.arg),
+            Base::Apply(apply) => apply.args.iter().any(depends_on_x),
+            Base::Derivative(derivative) => depends_on_x(&derivative.func),
+        },
+        Expression::Add(children) | Expression::Mul(children) => children.iter().any(depends_on_x),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Status;
+    use crate::parse::parse;
+
+    #[test]
+    fn test_domain_conditions() {
+        let cases = [
+            ("sin(x) + exp(x)", vec![]),
+            (
+                "log(x) / tan(x)",
+                vec![
+                    ("x > 0", Status::Unknown),
+                    ("tan(x) != 0", Status::Unknown),
+                    ("cos(x) != 0", Status::Unknown),
+                ],
+            ),
+            (
+                "log(x^2 + 1) + log(x^2 + 1)",
+                vec![("x^2 + 1 > 0", Status::Unknown)],
+            ),
+            (
+                "x^-2 + log(2)",
+                vec![("x != 0", Status::Unknown), ("2 > 0", Status::Holds)],
+            ),
+            ("log(0)", vec![("0 > 0", Status::Violated)]),
+            (
+                "(1 - 1)^-1 * log(-x)",
+                vec![
+                    ("1 - 1 != 0", Status::Violated),
+                    ("-x > 0", Status::Unknown),
+                ],
+            ),
+            (
+                "log(log(1))",
+                vec![("log(1) > 0", Status::Violated), ("1 > 0", Status::Holds)],
+            ),
+            (
+                "log(log(-1))",
+                vec![
+                    ("log(-1) > 0", Status::Unknown),
+                    ("-1 > 0", Status::Violated),
+                ],
+            ),
+        ];
+
+        for case in cases {
+            let conditions: Vec<(String, Status)> = parse(case.0)
+                .unwrap()
+                .domain_conditions()
+                .iter()
+                .map(|c| (c.to_string(), c.status))
+                .collect();
+            let expected: Vec<(String, Status)> = case
+                .1
+                .iter()
+                .map(|(c, status)| (c.to_string(), *status))
+                .collect();
+            assert_eq!(conditions, expected, "{}", case.0);
+        }
+    }
+
+    #[test]
+    fn test_domain_violations() {
+        let expression = parse("log(0)").unwrap();
+        let derivative = expression.diff();
+
+        assert_eq!(derivative.to_string(), "0 / 0");
+        let violations: Vec<String> = derivative
+            .domain_violations()
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(violations, ["0 != 0"]);
+        assert!(parse("log(x)")
+            .unwrap()
+            .diff()
+            .domain_violations()
+            .is_empty());
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::{Base, Expression};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DotOptions {
+    /// Draw structurally identical subtrees once, with an edge from every
+    /// parent, instead of once per occurrence.
+    pub merge_identical: bool,
+}
+
+impl Expression {
+    /// Renders the tree as a Graphviz `digraph` with one node per
+    /// `Add`, `Mul` and `Base`.
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(&DotOptions::default())
+    }
+
+    pub fn to_dot_with(&self, options: &DotOptions) -> String {
+        let mut graph = Graph {
+            options,
+            nodes: String::new(),
+            edges: String::new(),
+            count: 0,
+            ids: HashMap::new(),
+        };
+        graph.node(self);
+        format!("digraph expression {{\n{}{}}}\n", graph.nodes, graph.edges)
+    }
+}
+
+struct Graph<'a> {
+    options: &'a DotOptions,
+    nodes: String,
+    edges: String,
+    count: usize,
+    ids: HashMap<&'a Expression, usize>,
+}
+
+impl<'a> Graph<'a> {
+    // Returns the id of the node drawn for `expression`.
+    fn node(&mut self, expression: &'a Expression) -> usize {
+        if self.options.merge_identical {
+            if let Some(&id) = self.ids.get(expression) {
+                return id;
+            }
+        }
+        let id = self.count;
+        self.count += 1;
+        if self.options.merge_identical {
+            self.ids.insert(expression, id);
+        }
+
+        let (label, children): (String, Vec<&Expression>) = match expression {
+            Expression::Add(add) => ("add".to_string(), add.iter().collect()),
+            Expression::Mul(mul) => ("mul".to_string(), mul.iter().collect()),
+            Expression::Base(base) => match base {
+                Base::Scaler(scaler) => (scaler.to_string(), vec![]),
+                Base::X(_) => ("x".to_string(), vec![]),
+                Base::Exp(exp) => ("exp".to_string(), vec![&exp.arg]),
+                Base::Pow(pow) => (format!("^{}", pow.exp), vec![&pow.arg]),
+                Base::Log(log) => ("log".to_string(), vec![&log.arg]),
+                Base::Sin(sin) => ("sin".to_string(), vec![&sin.arg]),
+                Base::Cos(cos) => ("cos".to_string(), vec![&cos.arg]),
+                Base::Tan(tan) => ("tan".to_string(), vec![&tan.arg]),
+                Base::Custom(custom) => (custom.name.clone(), vec![&custom.arg]),
+                Base::Apply(apply) => (
+                    apply.name.escape_default().to_string(),
+                    apply.args.iter().collect(),
+                ),
+                // The derivative of its child with respect to argument `#wrt`.
+                Base::Derivative(derivative) => (
+                    format!("d^{}/d#{}", derivative.order, derivative.wrt),
+                    vec![&derivative.func],
+                ),
+            },
+        };
+        let shape = if children.is_empty() {
+            "box"
+        } else {
+            "ellipse"
+        };
+        let _ = writeln!(
+            self.nodes,
+            "  n{} [label=\"{}\", shape={}];",
+            id, label, shape
+        );
+        for child in children {
+            let child = self.node(child);
+            let _ = writeln!(self.edges, "  n{} -> n{};", id, child);
+        }
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dot::DotOptions;
+    use crate::parse::parse;
+
+    #[test]
+    fn test_to_dot() {
+        let expression = parse("x^2 - sin(x)").unwrap();
+
+        assert_eq!(
+            expression.to_dot(),
+            "\
+digraph expression {
+  n0 [label=\"add\", shape=ellipse];
+  n1 [label=\"^2\", shape=ellipse];
+  n2 [label=\"x\", shape=box];
+  n3 [label=\"mul\", shape=ellipse];
+  n4 [label=\"-1\", shape=box];
+  n5 [label=\"sin\", shape=ellipse];
+  n6 [label=\"x\", shape=box];
+  n1 -> n2;
+  n0 -> n1;
+  n3 -> n4;
+  n5 -> n6;
+  n3 -> n5;
+  n0 -> n3;
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_to_dot_merge_identical() {
+        let options = DotOptions {
+            merge_identical: true,
+        };
+        let cases = [
+            ("x^2 - sin(x)", 14, 16),
+            ("exp(x) * sin(x)", 10, 13),
+            ("x * x * x", 6, 12),
+        ];
+
+        for case in cases {
+            let expression = parse(case.0).unwrap().diff();
+            let dot = expression.to_dot_with(&options);
+            let nodes = dot.matches("[label=").count();
+            assert_eq!(nodes, case.1, "{}", dot);
+            assert_eq!(dot.matches(" -> ").count(), case.2, "{}", dot);
+            assert!(expression.to_dot().matches("[label=").count() > nodes);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use crate::rng::Rng;
+use crate::simplify::{expanded_terms, MAX_EXPANDED_TERMS};
+use crate::Expression;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Equivalence {
+    Equal,
+    /// A point at which the two expressions evaluate to different values.
+    NotEqual(f64),
+    /// Too few sample points were inside the domain of both expressions.
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquivalenceOptions {
+    pub samples: usize,
+    /// Below this many usable points the answer is `Unknown`.
+    pub min_valid_samples: usize,
+    /// Relative tolerance, scaled by the larger magnitude (at least 1).
+    pub tolerance: f64,
+    pub low: f64,
+    pub high: f64,
+    /// Treat a point where only one side is defined as a witness of
+    /// inequality instead of skipping it.
+    pub strict_domain: bool,
+    pub seed: u64,
+}
+
+impl Default for EquivalenceOptions {
+    fn default() -> Self {
+        Self {
+            samples: 64,
+            min_valid_samples: 16,
+            tolerance: 1e-9,
+            low: -10.0,
+            high: 10.0,
+            strict_domain: false,
+            seed: 0x5eed,
+        }
+    }
+}
+
+impl Expression {
+    pub fn equivalent(&self, other: &Expression) -> Equivalence {
+        self.equivalent_with(other, &EquivalenceOptions::default())
+    }
+
+    /// Compares the expanded canonical forms first and, if they differ,
+    /// evaluates both sides at random points. Agreement on every usable
+    /// point is reported as `Equal`, which is only probabilistically true.
+    /// Expressions whose expansion would be large go straight to sampling.
+    pub fn equivalent_with(&self, other: &Expression, options: &EquivalenceOptions) -> Equivalence {
+        let small = |e: &Expression| expanded_terms(e) <= MAX_EXPANDED_TERMS;
+        if small(self) && small(other) && self.expand() == other.expand() {
+            return Equivalence::Equal;
+        }
+        let lhs = self.compile();
+        let rhs = other.compile();
+        let mut rng = Rng::new(options.seed);
+        let mut valid = 0;
+        for _ in 0..options.samples {
+            let x = rng.range(options.low, options.high);
+            let a = lhs.call(x).ok().filter(|v| v.is_finite());
+            let b = rhs.call(x).ok().filter(|v| v.is_finite());
+            match (a, b) {
+                (Some(a), Some(b)) => {
+                    valid += 1;
+                    let scale = a.abs().max(b.abs()).max(1.0);
+                    if (a - b).abs() > options.tolerance * scale {
+                        return Equivalence::NotEqual(x);
+                    }
+                }
+                (None, None) => {}
+                _ if options.strict_domain => return Equivalence::NotEqual(x),
+                _ => {}
+            }
+        }
+        if valid >= options.min_valid_samples {
+            Equivalence::Equal
+        } else {
+            Equivalence::Unknown
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::build::{cos, exp, log, pow, sin, x};
+    use crate::equivalence::{Equivalence, EquivalenceOptions};
+    use crate::{Expression, X};
+
+    #[test]
+    fn test_equivalent() {
+        let cases = [
+            (x() * 2, 2 * x(), true),
+            (
+                pow(sin(x()), 2) + pow(cos(x()), 2),
+                Expression::from(1),
+                true,
+            ),
+            (sin(x()), x(), false),
+        ];
+
+        for case in cases {
+            match case.0.equivalent(&case.1) {
+                Equivalence::Equal => assert!(case.2),
+                Equivalence::NotEqual(x) => {
+                    assert!(!case.2);
+                    assert_ne!(case.0.eval(x), case.1.eval(x));
+                }
+                Equivalence::Unknown => panic!("expected a decision"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_equivalent_domain() {
+        let log_square = log(pow(x(), 2));
+        let double_log = 2 * log(x());
+
+        assert_eq!(log_square.equivalent(&double_log), Equivalence::Equal);
+        assert!(matches!(
+            log_square.equivalent_with(
+                &double_log,
+                &EquivalenceOptions {
+                    strict_domain: true,
+                    ..EquivalenceOptions::default()
+                }
+            ),
+            Equivalence::NotEqual(x) if x < 0.0
+        ));
+        assert_eq!(
+            double_log.equivalent_with(
+                &double_log.substitute(&X {}, &Expression::from(-1)),
+                &EquivalenceOptions::default()
+            ),
+            Equivalence::Unknown
+        );
+    }
+
+    #[test]
+    fn test_equivalent_large() {
+        let cases = [
+            (pow(x() + 1, 30), pow(x() + 1, 29) * (x() + 1), true),
+            (pow(x() + 1, 30), pow(x() + 1, 30) + 1, false),
+            (
+                pow(sin(x()) + cos(x()) + exp(x()) + log(x()) + 1, 40),
+                pow(sin(x()) + cos(x()) + exp(x()) + log(x()) + 1, 40),
+                true,
+            ),
+        ];
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for case in cases {
+                let equal = case.0.equivalent(&case.1) == Equivalence::Equal;
+                sender.send((equal, case.2)).unwrap();
+            }
+        });
+        for _ in 0..3 {
+            let (equal, expected) = receiver
+                .recv_timeout(Duration::from_secs(5))
+                .expect("equivalence check timed out");
+            assert_eq!(equal, expected);
+        }
+    }
+}
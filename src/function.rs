@@ -0,0 +1,257 @@
+//! User-defined functions.
+//!
+//! `register` adds a named function to a registry shared by the whole
+//! program, after which `Custom` nodes with that name can be evaluated and
+//! differentiated, and the parsers accept calls to it. The derivative rule is
+//! an expression in `x`, which stands for the argument; the chain rule is
+//! applied on top of it, so `f(g(x)).diff()` is `f'(g(x)) * g'(x)`. The rule
+//! may call the function itself, e.g. `sigmoid(x) * (1 - sigmoid(x))`.
+//!
+//! A `Custom` node whose name is not registered is treated like a function
+//! without a definition: it evaluates to `NaN` and its derivative is left
+//! unevaluated.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use crate::{Apply, Custom, Derivative, Expression, X};
+
+pub struct Function {
+    /// Registered functions live until the program exits, so their names are
+    /// kept for good as well.
+    pub name: &'static str,
+    eval: Box<dyn Fn(f64) -> f64 + Send + Sync>,
+    /// The derivative in terms of `x`, which stands for the argument.
+    pub derivative: Expression,
+}
+
+impl Function {
+    pub fn eval(&self, x: f64) -> f64 {
+        (self.eval)(x)
+    }
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("name", &self.name)
+            .field("derivative", &self.derivative)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterError {
+    /// The name belongs to a built-in function or an s-expression operator.
+    Reserved(String),
+    InvalidName(String),
+}
+
+impl fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Reserved(name) => write!(f, "`{}` is reserved", name),
+            Self::InvalidName(name) => write!(f, "`{}` is not a valid function name", name),
+        }
+    }
+}
+
+impl std::error::Error for RegisterError {}
+
+const RESERVED: [&str; 11] = [
+    "exp",
+    "log",
+    "ln",
+    "sin",
+    "cos",
+    "tan",
+    "add",
+    "mul",
+    "pow",
+    "apply",
+    "derivative",
+];
+
+static REGISTRY: RwLock<BTreeMap<&'static str, Arc<Function>>> = RwLock::new(BTreeMap::new());
+
+/// Registers `name` with its value and derivative, replacing an earlier
+/// function of the same name.
+pub fn register(
+    name: &str,
+    eval: impl Fn(f64) -> f64 + Send + Sync + 'static,
+    derivative: Expression,
+) -> Result<Arc<Function>, RegisterError> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+    if !valid {
+        return Err(RegisterError::InvalidName(name.to_string()));
+    }
+    if RESERVED.contains(&name) {
+        return Err(RegisterError::Reserved(name.to_string()));
+    }
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    let name = match registry.get_key_value(name) {
+        Some((name, _)) => *name,
+        None => Box::leak(name.to_string().into_boxed_str()),
+    };
+    let function = Arc::new(Function {
+        name,
+        eval: Box::new(eval),
+        derivative,
+    });
+    registry.insert(name, function.clone());
+    Ok(function)
+}
+
+pub fn lookup(name: &str) -> Option<Arc<Function>> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(name)
+        .cloned()
+}
+
+impl Custom {
+    /// The derivative of the function itself, evaluated at the argument, or
+    /// `name'(arg)` left unevaluated if no function of this name is
+    /// registered.
+    pub(crate) fn outer_derivative(&self) -> Expression {
+        match lookup(&self.name) {
+            Some(function) => function.derivative.substitute(&X {}, &self.arg),
+            None => Derivative {
+                func: Box::new(Apply::new(self.name.clone(), vec![(*self.arg).clone()]).into()),
+                order: 1,
+                wrt: 0,
+            }
+            .into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{custom, pow, sin, x};
+    use crate::function::{lookup, register, RegisterError};
+    use crate::latex::parse_latex;
+    use crate::parse::parse;
+    use crate::verify::{check_derivative, VerifyOptions};
+    use crate::Expression;
+
+    fn register_sigmoid() {
+        let sigmoid = custom("sigmoid", x());
+        register(
+            "sigmoid",
+            |x| 1.0 / (1.0 + (-x).exp()),
+            sigmoid.clone() * (1 - sigmoid),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_register() {
+        register_sigmoid();
+        let cases = [
+            ("sin", RegisterError::Reserved("sin".to_string())),
+            ("pow", RegisterError::Reserved("pow".to_string())),
+            ("apply", RegisterError::Reserved("apply".to_string())),
+            (
+                "derivative",
+                RegisterError::Reserved("derivative".to_string()),
+            ),
+            ("2f", RegisterError::InvalidName("2f".to_string())),
+            ("", RegisterError::InvalidName("".to_string())),
+        ];
+
+        for case in cases {
+            assert_eq!(register(case.0, |x| x, x()).unwrap_err(), case.1);
+        }
+        assert_eq!(lookup("sigmoid").unwrap().name, "sigmoid");
+        assert!(lookup("unregistered").is_none());
+    }
+
+    #[test]
+    fn test_diff() {
+        register_sigmoid();
+        let sigmoid = |arg: Expression| custom("sigmoid", arg);
+        let cases = [
+            (
+                sigmoid(x()),
+                Expression::Mul(vec![sigmoid(x()) * (1 - sigmoid(x())), Expression::from(1)]),
+            ),
+            (
+                sigmoid(pow(x(), 2)),
+                Expression::Mul(vec![
+                    sigmoid(pow(x(), 2)) * (1 - sigmoid(pow(x(), 2))),
+                    pow(x(), 2).diff(),
+                ]),
+            ),
+        ];
+
+        for case in cases {
+            assert_eq!(case.0.diff(), case.1);
+        }
+        assert_eq!(
+            custom("unregistered", pow(x(), 2)).diff().to_string(),
+            "unregistered'(x^2) * 2 * x^1 * 1"
+        );
+    }
+
+    #[test]
+    fn test_parse_eval_display() {
+        register_sigmoid();
+        register("tanh", f64::tanh, 1 - pow(custom("tanh", x()), 2)).unwrap();
+        let cases = [
+            ("sigmoid(x)", 0.0, 0.5),
+            ("2 * sigmoid(sin(x))", 0.0, 1.0),
+            ("tanh(x) + 1", 1.0, 1.0_f64.tanh() + 1.0),
+        ];
+
+        for case in cases {
+            let expression = parse(case.0).unwrap();
+            assert_eq!(expression.to_string(), case.0);
+            assert_eq!(expression.eval(case.1), case.2);
+            assert_eq!(expression.compile().call(case.1), Ok(case.2));
+        }
+        assert_eq!(parse("sigmoid(x)").unwrap(), custom("sigmoid", x()),);
+        let report = check_derivative(
+            &parse("sigmoid(sin(x)) * tanh(x)").unwrap(),
+            &VerifyOptions::default(),
+        );
+        assert!(report.passes(1e-6), "{:?}", report);
+        assert!(custom("unregistered", sin(x())).eval(1.0).is_nan());
+    }
+
+    #[test]
+    fn test_formats() {
+        register("soft_plus", |x| x.exp().ln_1p(), custom("sigmoid", x())).unwrap();
+        let expression = 2 * custom("soft_plus", sin(x()));
+        let cases = [
+            (
+                expression.to_latex(),
+                "2\\operatorname{soft\\_plus}\\left(\\sin\\left(x\\right)\\right)",
+            ),
+            (expression.to_sexpr(), "(mul 2 (soft_plus (sin x)))"),
+            (
+                expression.to_json().to_string(),
+                r#"{"version":1,"expression":{"op":"mul","factors":[{"op":"const","value":2},{"op":"call","name":"soft_plus","arg":{"op":"sin","arg":{"op":"var"}}}]}}"#,
+            ),
+        ];
+
+        for case in &cases {
+            assert_eq!(case.0, case.1);
+        }
+        assert_eq!(parse_latex(&cases[0].0), Ok(expression.clone()));
+        assert_eq!(Expression::from_sexpr(&cases[1].0), Ok(expression.clone()));
+        assert_eq!(Expression::from_json_str(&cases[2].0), Ok(expression));
+
+        let unknown = r#"{"version":1,"expression":{"op":"call","name":"g","arg":{"op":"var"}}}"#;
+        assert_eq!(
+            Expression::from_json_str(unknown).unwrap_err().to_string(),
+            "unknown function `g` at $.expression.name"
+        );
+        assert!(parse_latex("\\operatorname{g}(x)").is_err());
+        assert!(Expression::from_sexpr("(g x)").is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! Implicit differentiation.
+//!
+//! The dependent variable is written as the undefined function `y(x)`, e.g.
+//! `x^2 + y^2 = 25` is `Equation::new(pow(x(), 2) + pow(y, 2), 25)` with
+//! `y = apply("y", [x()])`. Differentiating both sides gives an equation that
+//! is linear in `y'(x)`, which is then solved for it.
+
+use std::fmt;
+
+use crate::simplify::{expanded_terms, MAX_EXPANDED_TERMS};
+use crate::visit::{fold_expression, walk_base, Fold, Visitor};
+use crate::{Apply, Base, Derivative, Expression, Scaler, X};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Equation {
+    pub lhs: Expression,
+    pub rhs: Expression,
+}
+
+impl Equation {
+    pub fn new(lhs: impl Into<Expression>, rhs: impl Into<Expression>) -> Self {
+        Self {
+            lhs: lhs.into(),
+            rhs: rhs.into(),
+        }
+    }
+}
+
+impl fmt::Display for Equation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} = {}", self.lhs, self.rhs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImplicitError {
+    /// The derivative of the equation does not involve `y'(x)`.
+    Independent(String),
+    /// The equation already contains a derivative of `y`.
+    Differential(String),
+}
+
+impl fmt::Display for ImplicitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Independent(y) => write!(f, "the equation does not determine `{}'`", y),
+            Self::Differential(y) => write!(f, "the equation contains a derivative of `{}`", y),
+        }
+    }
+}
+
+impl std::error::Error for ImplicitError {}
+
+/// Returns `dy/dx` for the curve given by `equation`, where `y` is the name of
+/// the undefined function `y(x)`. The result may contain `y(x)` itself.
+pub fn implicit_diff(equation: &Equation, y: &str, x: &X) -> Result<Expression, ImplicitError> {
+    let function = Expression::Base(Base::Apply(Apply::new(
+        y,
+        vec![Expression::Base(Base::X(x.clone()))],
+    )));
+    let mut finder = FindDerivative { y, found: false };
+    finder.visit_expression(&equation.lhs);
+    finder.visit_expression(&equation.rhs);
+    if finder.found {
+        return Err(ImplicitError::Differential(y.to_string()));
+    }
+
+    // d/dx (lhs - rhs) = a + b * y'(x), since the chain rule is linear in the
+    // derivative of the inner function.
+    let derivative = (equation.lhs.clone() - equation.rhs.clone()).diff();
+    let target = Expression::Base(Base::Derivative(Derivative {
+        func: Box::new(function),
+        order: 1,
+        wrt: 0,
+    }));
+    let replace = |value: Scaler| {
+        Replace {
+            target: &target,
+            replacement: Expression::from(value),
+        }
+        .fold_expression(&derivative)
+    };
+    let a = collect(replace(0));
+    let b = collect(replace(1) - a.clone());
+    if b == Expression::from(0) {
+        return Err(ImplicitError::Independent(y.to_string()));
+    }
+    Ok((-a / b).simplify())
+}
+
+// Expanding collects the `y'(x)` terms, unless it would be too costly.
+fn collect(expression: Expression) -> Expression {
+    if expanded_terms(&expression) <= MAX_EXPANDED_TERMS {
+        expression.expand()
+    } else {
+        expression.simplify()
+    }
+}
+
+// Looks for any derivative of `y`.
+struct FindDerivative<'a> {
+    y: &'a str,
+    found: bool,
+}
+
+impl Visitor for FindDerivative<'_> {
+    fn visit_base(&mut self, base: &Base) {
+        if let Base::Derivative(derivative) = base {
+            if derivative.apply().is_some_and(|apply| apply.name == self.y) {
+                self.found = true;
+            }
+        }
+        walk_base(self, base)
+    }
+}
+
+struct Replace<'a> {
+    target: &'a Expression,
+    replacement: Expression,
+}
+
+impl Fold for Replace<'_> {
+    fn fold_expression(&mut self, expression: &Expression) -> Expression {
+        if expression == self.target {
+            self.replacement.clone()
+        } else {
+            fold_expression(self, expression)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::build::{apply, cos, exp, pow, sin, x};
+    use crate::implicit::{implicit_diff, Equation, ImplicitError};
+    use crate::{Derivative, Expression, X};
+
+    fn y() -> Expression {
+        apply("y", [x()])
+    }
+
+    #[test]
+    fn test_implicit_diff() {
+        let cases = [
+            (Equation::new(pow(x(), 2) + pow(y(), 2), 25), "-x / y(x)"),
+            (
+                Equation::new(sin(x() * y()), x()),
+                "-(cos(x * y(x)) * y(x) - 1) / (x * cos(x * y(x)))",
+            ),
+            (Equation::new(exp(y()), x()), "exp(y(x))^-1"),
+            (Equation::new(y(), cos(x())), "-sin(x)"),
+        ];
+
+        for case in cases {
+            let derivative = implicit_diff(&case.0, "y", &X {}).unwrap();
+            assert_eq!(derivative.to_string(), case.1, "{}", case.0);
+        }
+    }
+
+    #[test]
+    fn test_implicit_diff_large() {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let lhs = pow(sin(x()) + cos(x()) + exp(x()) + y() + 1, 40);
+            sender
+                .send(implicit_diff(&Equation::new(lhs, x()), "y", &X {}).is_ok())
+                .unwrap();
+        });
+        let solved = receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("implicit differentiation timed out");
+        assert!(solved);
+    }
+
+    #[test]
+    fn test_implicit_diff_error() {
+        let cases = [
+            (
+                Equation::new(pow(x(), 2), 4),
+                ImplicitError::Independent("y".to_string()),
+            ),
+            (
+                Equation::new(Derivative::new(y(), 1, 0).unwrap(), y()),
+                ImplicitError::Differential("y".to_string()),
+            ),
+        ];
+
+        for case in cases {
+            assert_eq!(implicit_diff(&case.0, "y", &X {}), Err(case.1));
+        }
+    }
+}
@@ -0,0 +1,407 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// A JSON document. Object members keep their insertion order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn object<const N: usize>(members: [(&str, Value); N]) -> Self {
+        Self::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// Looks up a member of an object.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Self::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Self::String(s)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(x: f64) -> Self {
+        Self::Float(x)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Int(n) => write!(f, "{}", n),
+            // JSON has no representation for NaN or infinities.
+            Self::Float(x) if !x.is_finite() => write!(f, "null"),
+            Self::Float(x) => write!(f, "{:?}", x),
+            Self::String(s) => write_string(s, f),
+            Self::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Self::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(key, f)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(s: &str, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    pub message: String,
+    /// Byte offset into the input.
+    pub position: usize,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl FromStr for Value {
+    type Err = JsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s)
+    }
+}
+
+pub fn parse(input: &str) -> Result<Value, JsonError> {
+    let mut parser = Parser { input, position: 0 };
+    let value = parser.value()?;
+    parser.whitespace();
+    if parser.position != input.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> JsonError {
+        JsonError {
+            message: message.to_string(),
+            position: self.position,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.position).copied()
+    }
+
+    fn whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), JsonError> {
+        self.whitespace();
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("expected `{}`", c as char)));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Value, JsonError> {
+        self.whitespace();
+        match self.peek() {
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'"') => Ok(Value::String(self.string()?)),
+            Some(b'[') => {
+                self.position += 1;
+                let mut values = vec![];
+                self.whitespace();
+                if self.peek() == Some(b']') {
+                    self.position += 1;
+                    return Ok(Value::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    self.whitespace();
+                    match self.peek() {
+                        Some(b',') => self.position += 1,
+                        Some(b']') => {
+                            self.position += 1;
+                            return Ok(Value::Array(values));
+                        }
+                        _ => return Err(self.error("expected `,` or `]`")),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.position += 1;
+                let mut members = vec![];
+                self.whitespace();
+                if self.peek() == Some(b'}') {
+                    self.position += 1;
+                    return Ok(Value::Object(members));
+                }
+                loop {
+                    self.whitespace();
+                    if self.peek() != Some(b'"') {
+                        return Err(self.error("expected a member name"));
+                    }
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    members.push((key, self.value()?));
+                    self.whitespace();
+                    match self.peek() {
+                        Some(b',') => self.position += 1,
+                        Some(b'}') => {
+                            self.position += 1;
+                            return Ok(Value::Object(members));
+                        }
+                        _ => return Err(self.error("expected `,` or `}`")),
+                    }
+                }
+            }
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn literal(&mut self, literal: &str, value: Value) -> Result<Value, JsonError> {
+        if !self.input[self.position..].starts_with(literal) {
+            return Err(self.error("expected a value"));
+        }
+        self.position += literal.len();
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<Value, JsonError> {
+        let start = self.position;
+        let mut integer = true;
+        while let Some(c) = self.peek() {
+            match c {
+                b'0'..=b'9' | b'-' | b'+' => {}
+                b'.' | b'e' | b'E' => integer = false,
+                _ => break,
+            }
+            self.position += 1;
+        }
+        let text = &self.input[start..self.position];
+        if integer {
+            if let Ok(n) = text.parse() {
+                return Ok(Value::Int(n));
+            }
+        }
+        text.parse().map(Value::Float).map_err(|_| JsonError {
+            message: "invalid number".to_string(),
+            position: start,
+        })
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        let start = self.position;
+        self.position += 1;
+        let mut s = String::new();
+        loop {
+            let rest = &self.input[self.position..];
+            let c = match rest.chars().next() {
+                Some(c) => c,
+                None => {
+                    return Err(JsonError {
+                        message: "unterminated string".to_string(),
+                        position: start,
+                    })
+                }
+            };
+            self.position += c.len_utf8();
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escape = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.position += 1;
+                    s.push(match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex()?;
+                            if (0xd800..0xdc00).contains(&code)
+                                && self.input[self.position..].starts_with("\\u")
+                            {
+                                self.position += 2;
+                                let low = self.hex()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err(self.error("invalid escape"));
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            char::from_u32(code).ok_or_else(|| self.error("invalid escape"))?
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    });
+                }
+                c if (c as u32) < 0x20 => return Err(self.error("control character in string")),
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn hex(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .input
+            .get(self.position..self.position + 4)
+            .ok_or_else(|| self.error("invalid escape"))?;
+        let code = u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid escape"))?;
+        self.position += 4;
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{parse, JsonError, Value};
+
+    #[test]
+    fn test_display() {
+        let cases = [
+            (Value::Null, "null"),
+            (Value::Int(-3), "-3"),
+            (Value::Float(1.5), "1.5"),
+            (Value::Float(2.0), "2.0"),
+            (Value::Float(f64::NAN), "null"),
+            (Value::from("a \"b\"\n\u{1}"), r#""a \"b\"\n\u0001""#),
+            (
+                Value::object([
+                    ("list", Value::Array(vec![Value::Bool(true), Value::Null])),
+                    ("name", Value::from("x")),
+                ]),
+                r#"{"list":[true,null],"name":"x"}"#,
+            ),
+        ];
+
+        for case in cases {
+            assert_eq!(case.0.to_string(), case.1);
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let cases = [
+            ("null", Value::Null),
+            (" -12 ", Value::Int(-12)),
+            ("1.5e2", Value::Float(150.0)),
+            (
+                r#""a\"b\u00e9\ud83d\ude00""#,
+                Value::from("a\"b\u{e9}\u{1f600}"),
+            ),
+            (
+                r#"{"id": [1, true], "expr": "x"}"#,
+                Value::object([
+                    ("id", Value::Array(vec![Value::Int(1), Value::Bool(true)])),
+                    ("expr", Value::from("x")),
+                ]),
+            ),
+        ];
+
+        for case in cases {
+            assert_eq!(parse(case.0), Ok(case.1.clone()));
+            assert_eq!(parse(&case.1.to_string()), Ok(case.1));
+        }
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let cases = [
+            ("", "unexpected end of input", 0),
+            ("{\"a\" 1}", "expected `:`", 5),
+            ("[1 2]", "expected `,` or `]`", 3),
+            ("\"abc", "unterminated string", 0),
+            ("nul", "expected a value", 0),
+            ("1 2", "trailing characters", 2),
+        ];
+
+        for case in cases {
+            assert_eq!(
+                parse(case.0),
+                Err(JsonError {
+                    message: case.1.to_string(),
+                    position: case.2,
+                })
+            );
+        }
+    }
+}
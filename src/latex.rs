@@ -0,0 +1,885 @@
+use crate::function::lookup;
+use crate::parse::{negate, ParseError};
+use crate::{Base, Cos, Custom, Exp, Expression, Log, Pow, Scaler, Sin, Tan, X};
+
+impl Expression {
+    /// Renders the expression as LaTeX math, e.g. `\sin\left(x^{2}\right)`.
+    pub fn to_latex(&self) -> String {
+        self.to_latex_with_variable("x")
+    }
+
+    /// Like `to_latex`, but the variable is written as `variable`.
+    pub fn to_latex_with_variable(&self, variable: &str) -> String {
+        Printer { variable }.expression(self)
+    }
+}
+
+/// Renders `\frac{d}{dx} expression = derivative`, with `d^{n}` and `dx^{n}`
+/// for higher orders.
+pub fn derivative_equation(
+    expression: &Expression,
+    derivative: &Expression,
+    order: usize,
+    variable: &str,
+) -> String {
+    let printer = Printer { variable };
+    let operator = match order {
+        1 => format!("\\frac{{d}}{{d{}}}", variable),
+        n => format!("\\frac{{d^{{{}}}}}{{d{}^{{{}}}}}", n, variable, n),
+    };
+    let operand = match expression {
+        Expression::Base(Base::Scaler(_) | Base::X(_)) => printer.expression(expression),
+        _ => printer.parenthesized(expression),
+    };
+    format!(
+        "{} {} = {}",
+        operator,
+        operand,
+        printer.expression(derivative)
+    )
+}
+
+fn operator_name(name: &str) -> String {
+    format!("\\operatorname{{{}}}", name.replace('_', "\\_"))
+}
+
+// Single letters such as `f` are set in italics like the variable.
+fn function_name(name: &str) -> String {
+    match name.chars().count() {
+        1 => name.to_string(),
+        _ => operator_name(name),
+    }
+}
+
+struct Printer<'a> {
+    variable: &'a str,
+}
+
+impl Printer<'_> {
+    fn expression(&self, expression: &Expression) -> String {
+        match expression {
+            Expression::Base(base) => self.base(base),
+            Expression::Add(add) => self.add(add),
+            Expression::Mul(mul) => self.mul(mul),
+        }
+    }
+
+    fn base(&self, base: &Base) -> String {
+        match base {
+            Base::Scaler(scaler) => scaler.to_string(),
+            Base::X(_) => self.variable.to_string(),
+            Base::Exp(exp) => format!("e^{{{}}}", self.expression(&exp.arg)),
+            Base::Pow(pow) if pow.exp < 0 && pow.exp != isize::MIN => {
+                self.fraction(&[], &[reciprocal(pow)])
+            }
+            Base::Pow(pow) => format!("{}^{{{}}}", self.pow_base(&pow.arg), pow.exp),
+            Base::Log(log) => self.function("\\ln", &log.arg),
+            Base::Sin(sin) => self.function("\\sin", &sin.arg),
+            Base::Cos(cos) => self.function("\\cos", &cos.arg),
+            Base::Tan(tan) => self.function("\\tan", &tan.arg),
+            Base::Custom(custom) => self.function(&operator_name(&custom.name), &custom.arg),
+            Base::Apply(apply) => self.call(&function_name(&apply.name), &apply.args),
+            Base::Derivative(derivative) => match derivative.apply() {
+                Some(apply) => {
+                    let marks = match derivative.orders()[..] {
+                        [order] if order <= 3 => "'".repeat(order),
+                        ref orders => format!(
+                            "^{{({})}}",
+                            orders
+                                .iter()
+                                .map(|order| order.to_string())
+                                .collect::<Vec<_>>()
+                                .join(",")
+                        ),
+                    };
+                    self.call(
+                        &format!("{}{}", function_name(&apply.name), marks),
+                        &apply.args,
+                    )
+                }
+                None => format!(
+                    "\\partial_{{{}}}^{{{}}}{}",
+                    derivative.wrt,
+                    derivative.order,
+                    self.parenthesized(&derivative.func)
+                ),
+            },
+        }
+    }
+
+    fn call(&self, name: &str, args: &[Expression]) -> String {
+        let args: Vec<String> = args.iter().map(|arg| self.expression(arg)).collect();
+        format!("{}\\left({}\\right)", name, args.join(", "))
+    }
+
+    fn function(&self, name: &str, arg: &Expression) -> String {
+        format!("{}{}", name, self.parenthesized(arg))
+    }
+
+    fn parenthesized(&self, expression: &Expression) -> String {
+        format!("\\left({}\\right)", self.expression(expression))
+    }
+
+    fn pow_base(&self, arg: &Expression) -> String {
+        match arg {
+            Expression::Base(Base::Scaler(scaler)) if *scaler >= 0 => scaler.to_string(),
+            Expression::Base(Base::X(_)) => self.variable.to_string(),
+            // Functions keep their own parentheses, as in `\sin\left(x\right)^{2}`.
+            Expression::Base(
+                Base::Log(_)
+                | Base::Sin(_)
+                | Base::Cos(_)
+                | Base::Tan(_)
+                | Base::Custom(_)
+                | Base::Apply(_)
+                | Base::Derivative(_),
+            ) => self.expression(arg),
+            Expression::Mul(mul) if mul.len() == 1 => self.pow_base(&mul[0]),
+            Expression::Add(add) if add.len() == 1 => self.pow_base(&add[0]),
+            _ => self.parenthesized(arg),
+        }
+    }
+
+    fn add(&self, add: &[Expression]) -> String {
+        if add.is_empty() {
+            return "0".to_string();
+        }
+        let mut latex = String::new();
+        for (i, term) in add.iter().enumerate() {
+            let term = match term {
+                Expression::Add(inner) if i > 0 && inner.len() > 1 => self.parenthesized(term),
+                _ => self.expression(term),
+            };
+            if i == 0 {
+                latex.push_str(&term);
+            } else if let Some(term) = term.strip_prefix('-') {
+                latex.push_str(" - ");
+                latex.push_str(term);
+            } else {
+                latex.push_str(" + ");
+                latex.push_str(&term);
+            }
+        }
+        latex
+    }
+
+    fn mul(&self, mul: &[Expression]) -> String {
+        let mut numerator = vec![];
+        let mut denominator = vec![];
+        for factor in mul {
+            match factor {
+                Expression::Base(Base::Pow(pow)) if pow.exp < 0 && pow.exp != isize::MIN => {
+                    denominator.push(reciprocal(pow))
+                }
+                factor => numerator.push(factor),
+            }
+        }
+        if denominator.is_empty() {
+            return self.factors(&numerator);
+        }
+        self.fraction(&numerator, &denominator)
+    }
+
+    fn fraction(&self, numerator: &[&Expression], denominator: &[Expression]) -> String {
+        let numerator_text = self.factors(numerator);
+        // Pull a leading minus sign out of the fraction, unless it belongs to
+        // the first term of a sum.
+        let (sign, numerator_text) = match (numerator, numerator_text.strip_prefix('-')) {
+            ([Expression::Add(add)], _) if add.len() > 1 => ("", numerator_text.as_str()),
+            (_, Some(rest)) => ("-", rest),
+            (_, None) => ("", numerator_text.as_str()),
+        };
+        format!(
+            "{}\\frac{{{}}}{{{}}}",
+            sign,
+            numerator_text,
+            self.factors(&denominator.iter().collect::<Vec<_>>())
+        )
+    }
+
+    // Juxtaposes a leading integer coefficient with what follows, as in `2x`,
+    // and separates every other pair of factors with `\cdot`.
+    fn factors(&self, factors: &[&Expression]) -> String {
+        let (sign, factors) = match factors {
+            [Expression::Base(Base::Scaler(-1)), rest @ ..] if !rest.is_empty() => ("-", rest),
+            factors => ("", factors),
+        };
+        if factors.is_empty() {
+            return "1".to_string();
+        }
+        // A sum is wrapped unless it is the only factor, counting the `-1`.
+        let alone = factors.len() == 1 && sign.is_empty();
+        let mut latex = sign.to_string();
+        for (i, factor) in factors.iter().enumerate() {
+            let text = self.expression(factor);
+            let wrap = match factor {
+                Expression::Add(add) => add.len() != 1 && !alone,
+                _ => i > 0 && text.starts_with('-'),
+            };
+            let text = if wrap {
+                format!("\\left({}\\right)", text)
+            } else {
+                text
+            };
+            if i > 0 {
+                let coefficient = matches!(factors[i - 1], Expression::Base(Base::Scaler(_)));
+                let numeric = text.starts_with(|c: char| c.is_ascii_digit());
+                if !(i == 1 && coefficient && !numeric) {
+                    latex.push_str(" \\cdot ");
+                }
+            }
+            latex.push_str(&text);
+        }
+        latex
+    }
+}
+
+fn reciprocal(pow: &Pow) -> Expression {
+    if pow.exp == -1 {
+        *pow.arg.clone()
+    } else {
+        Expression::Base(Base::Pow(Pow {
+            exp: -pow.exp,
+            arg: pow.arg.clone(),
+        }))
+    }
+}
+
+/// Parses a subset of LaTeX math such as `\sin(x^2) + \frac{1}{x}` or
+/// `e^{2x}`: `\frac`, `\sqrt`, `^{...}`, `\ln`, `\log`, `\exp`, `\sin`,
+/// `\cos`, `\tan`, `\left( \right)`, `\cdot`, `\times` and implicit
+/// multiplication. `\sqrt[n]{a}` becomes `e^{\ln(a) / n}`, which agrees with
+/// the root for `a > 0`.
+pub fn parse_latex(input: &str) -> Result<Expression, ParseError> {
+    parse_latex_with_variable(input, "x")
+}
+
+/// Like `parse_latex`, but the variable is spelled `variable` instead of `x`.
+pub fn parse_latex_with_variable(input: &str, variable: &str) -> Result<Expression, ParseError> {
+    Parser {
+        tokens: tokenize(input)?,
+        index: 0,
+        variable,
+    }
+    .parse()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(Scaler),
+    Letter(char),
+    Command(String),
+    /// The name in `\operatorname{name}`.
+    Operator(String),
+    Plus,
+    Minus,
+    Times,
+    Slash,
+    Caret,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    End,
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    variable: &'a str,
+}
+
+impl Parser<'_> {
+    fn parse(mut self) -> Result<Expression, ParseError> {
+        let expression = self.expression()?;
+        match self.peek() {
+            Token::End => Ok(expression),
+            Token::RParen => Err(self.error("unmatched `)`")),
+            Token::RBrace => Err(self.error("unmatched `}`")),
+            _ => Err(self.error("expected an operator")),
+        }
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index].0
+    }
+
+    fn position(&self) -> usize {
+        self.tokens[self.index].1
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.index].0.clone();
+        if token != Token::End {
+            self.index += 1;
+        }
+        token
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            message: message.to_string(),
+            position: self.position(),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expression, ParseError> {
+        let mut terms = vec![self.term()?];
+        loop {
+            match self.peek() {
+                Token::Plus => {
+                    self.next();
+                    terms.push(self.term()?);
+                }
+                Token::Minus => {
+                    self.next();
+                    let term = self.term()?;
+                    terms.push(negate(term));
+                }
+                _ => break,
+            }
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expression::Add(terms)
+        })
+    }
+
+    fn term(&mut self) -> Result<Expression, ParseError> {
+        let mut factors = vec![self.unary()?];
+        loop {
+            match self.peek() {
+                Token::Times => {
+                    self.next();
+                    factors.push(self.unary()?);
+                }
+                Token::Slash => {
+                    self.next();
+                    let factor = self.unary()?;
+                    factors.push(Expression::Base(Base::Pow(Pow {
+                        exp: -1,
+                        arg: Box::new(factor),
+                    })));
+                }
+                // Implicit multiplication, as in `2x` or `x\sin x`.
+                Token::Number(_)
+                | Token::Letter(_)
+                | Token::Command(_)
+                | Token::Operator(_)
+                | Token::LParen
+                | Token::LBrace => factors.push(self.power()?),
+                _ => break,
+            }
+        }
+        Ok(if factors.len() == 1 {
+            factors.remove(0)
+        } else {
+            Expression::Mul(factors)
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expression, ParseError> {
+        match self.peek() {
+            Token::Minus => {
+                self.next();
+                Ok(negate(self.unary()?))
+            }
+            Token::Plus => {
+                self.next();
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<Expression, ParseError> {
+        if *self.peek() == Token::Letter('e') && self.variable != "e" {
+            self.next();
+            if *self.peek() != Token::Caret {
+                return Ok(Expression::Base(Base::Exp(Exp {
+                    arg: Box::new(Expression::Base(Base::Scaler(1))),
+                })));
+            }
+            self.next();
+            let arg = match self.peek() {
+                Token::LBrace => self.group()?,
+                _ => self.primary()?,
+            };
+            return Ok(Expression::Base(Base::Exp(Exp { arg: Box::new(arg) })));
+        }
+        let base = self.primary()?;
+        if *self.peek() != Token::Caret {
+            return Ok(base);
+        }
+        self.next();
+        let exp = self.exponent()?;
+        Ok(Expression::Base(Base::Pow(Pow {
+            exp,
+            arg: Box::new(base),
+        })))
+    }
+
+    // `^2`, `^{-12}` and `^{(-1)}`. A bare exponent is a single digit, as in
+    // LaTeX itself.
+    fn exponent(&mut self) -> Result<isize, ParseError> {
+        let position = self.position();
+        let error = ParseError {
+            message: "exponent must be an integer".to_string(),
+            position,
+        };
+        if let Token::Number(n) = *self.peek() {
+            if !(0..10).contains(&n) {
+                return Err(ParseError {
+                    message: "exponents with several digits need braces".to_string(),
+                    position,
+                });
+            }
+            self.next();
+            return Ok(n);
+        }
+        match self.group()? {
+            Expression::Base(Base::Scaler(n)) => Ok(n),
+            _ => Err(error),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expression, ParseError> {
+        let position = self.position();
+        match self.next() {
+            Token::Number(n) => Ok(Expression::Base(Base::Scaler(n))),
+            Token::Letter(c) if self.variable.len() == 1 && self.variable.starts_with(c) => {
+                Ok(Expression::Base(Base::X(X {})))
+            }
+            Token::Letter(c) => Err(ParseError {
+                message: format!("unknown variable `{}`", c),
+                position,
+            }),
+            Token::Command(name) => self.command(&name, position),
+            Token::Operator(name) if lookup(&name).is_some() => self.function(&name, position),
+            Token::Operator(name) => Err(ParseError {
+                message: format!("unknown function `{}`", name),
+                position,
+            }),
+            Token::LParen | Token::LBrace => {
+                self.index -= 1;
+                self.group()
+            }
+            Token::End => Err(ParseError {
+                message: "unexpected end of input".to_string(),
+                position,
+            }),
+            _ => Err(ParseError {
+                message: "expected a number, variable or command".to_string(),
+                position,
+            }),
+        }
+    }
+
+    fn command(&mut self, name: &str, position: usize) -> Result<Expression, ParseError> {
+        let error = |message: String| ParseError { message, position };
+        match name {
+            "frac" => {
+                if *self.peek() != Token::LBrace {
+                    return Err(error("`\\frac` expects two `{...}` arguments".to_string()));
+                }
+                let numerator = self.group()?;
+                if *self.peek() != Token::LBrace {
+                    return Err(error("`\\frac` expects two `{...}` arguments".to_string()));
+                }
+                let denominator = self.group()?;
+                Ok(Expression::Mul(vec![
+                    numerator,
+                    Expression::Base(Base::Pow(Pow {
+                        exp: -1,
+                        arg: Box::new(denominator),
+                    })),
+                ]))
+            }
+            "sqrt" => {
+                let degree = if *self.peek() == Token::LBracket {
+                    self.next();
+                    let degree = match self.next() {
+                        Token::Number(n) if n > 1 => n,
+                        _ => return Err(error("`\\sqrt[n]` needs an integer n > 1".to_string())),
+                    };
+                    if self.next() != Token::RBracket {
+                        return Err(error("`\\sqrt[n]` needs an integer n > 1".to_string()));
+                    }
+                    degree
+                } else {
+                    2
+                };
+                if *self.peek() != Token::LBrace {
+                    return Err(error("`\\sqrt` expects a `{...}` argument".to_string()));
+                }
+                let radicand = self.group()?;
+                Ok(Expression::Base(Base::Exp(Exp {
+                    arg: Box::new(Expression::Mul(vec![
+                        Expression::Base(Base::Log(Log {
+                            arg: Box::new(radicand),
+                        })),
+                        Expression::Base(Base::Pow(Pow {
+                            exp: -1,
+                            arg: Box::new(Expression::Base(Base::Scaler(degree))),
+                        })),
+                    ])),
+                })))
+            }
+            "exp" | "ln" | "log" | "sin" | "cos" | "tan" => self.function(name, position),
+            name => Err(error(format!("unknown command `\\{}`", name))),
+        }
+    }
+
+    // A function call such as `\sin x`, `\cos^2(x)` or `\operatorname{f}{x}`.
+    fn function(&mut self, name: &str, position: usize) -> Result<Expression, ParseError> {
+        let error = |message: String| ParseError { message, position };
+        // `\sin^2 x` squares the function, not its argument.
+        let exp = if *self.peek() == Token::Caret {
+            self.next();
+            Some(self.exponent()?)
+        } else {
+            None
+        };
+        let arg = Box::new(match self.peek() {
+            Token::LParen | Token::LBrace => self.group()?,
+            Token::End => return Err(error(format!("`\\{}` needs an argument", name))),
+            _ => self.power()?,
+        });
+        let function = Expression::Base(match name {
+            "exp" => Base::Exp(Exp { arg }),
+            "ln" | "log" => Base::Log(Log { arg }),
+            "sin" => Base::Sin(Sin { arg }),
+            "cos" => Base::Cos(Cos { arg }),
+            "tan" => Base::Tan(Tan { arg }),
+            _ => Base::Custom(Custom {
+                name: name.to_string(),
+                arg,
+            }),
+        });
+        Ok(match exp {
+            Some(exp) => Expression::Base(Base::Pow(Pow {
+                exp,
+                arg: Box::new(function),
+            })),
+            None => function,
+        })
+    }
+
+    // A parenthesized or braced group.
+    fn group(&mut self) -> Result<Expression, ParseError> {
+        let open = self.position();
+        let close = match self.next() {
+            Token::LParen => Token::RParen,
+            Token::LBrace => Token::RBrace,
+            _ => {
+                return Err(ParseError {
+                    message: "expected `(` or `{`".to_string(),
+                    position: open,
+                })
+            }
+        };
+        let expression = self.expression()?;
+        if *self.peek() == close {
+            self.next();
+            return Ok(expression);
+        }
+        let (unclosed, expected) = match close {
+            Token::RParen => ("unclosed `(`", "expected `)`"),
+            _ => ("unclosed `{`", "expected `}`"),
+        };
+        match self.peek() {
+            Token::End => Err(ParseError {
+                message: unclosed.to_string(),
+                position: open,
+            }),
+            _ => Err(self.error(expected)),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+    while let Some(&(position, c)) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '0'..='9' => {
+                let mut end = position;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !c.is_ascii_digit() {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                if let Some(&(_, '.')) = chars.peek() {
+                    return Err(ParseError {
+                        message: "only integer constants are supported".to_string(),
+                        position,
+                    });
+                }
+                match input[position..end].parse() {
+                    Ok(n) => Token::Number(n),
+                    Err(_) => {
+                        return Err(ParseError {
+                            message: "integer constant is too large".to_string(),
+                            position,
+                        })
+                    }
+                }
+            }
+            '\\' => {
+                chars.next();
+                let mut end = position + 1;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !c.is_ascii_alphabetic() {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                let name = &input[position + 1..end];
+                match name {
+                    // Spacing commands such as `\,` and `\ `.
+                    "" => match chars.next() {
+                        Some((_, ',' | ';' | ':' | '!' | ' ')) => continue,
+                        _ => {
+                            return Err(ParseError {
+                                message: "expected a command name after `\\`".to_string(),
+                                position,
+                            })
+                        }
+                    },
+                    "quad" | "qquad" => continue,
+                    "operatorname" => {
+                        if chars.next().map(|(_, c)| c) != Some('{') {
+                            return Err(ParseError {
+                                message: "`\\operatorname` expects a `{...}` name".to_string(),
+                                position,
+                            });
+                        }
+                        let mut name = String::new();
+                        loop {
+                            match chars.next() {
+                                Some((_, '}')) => break,
+                                Some((_, '\\')) if chars.peek().map(|&(_, c)| c) == Some('_') => {}
+                                Some((_, c)) if c.is_alphanumeric() || c == '_' => name.push(c),
+                                _ => {
+                                    return Err(ParseError {
+                                        message: "`\\operatorname` expects a `{...}` name"
+                                            .to_string(),
+                                        position,
+                                    })
+                                }
+                            }
+                        }
+                        Token::Operator(name)
+                    }
+                    "cdot" | "times" => Token::Times,
+                    "left" | "right" => {
+                        while let Some(&(_, c)) = chars.peek() {
+                            if !c.is_whitespace() {
+                                break;
+                            }
+                            chars.next();
+                        }
+                        match (name, chars.next()) {
+                            ("left", Some((_, '('))) => Token::LParen,
+                            ("right", Some((_, ')'))) => Token::RParen,
+                            _ => {
+                                return Err(ParseError {
+                                    message: format!(
+                                        "`\\{}` must be followed by a parenthesis",
+                                        name
+                                    ),
+                                    position,
+                                })
+                            }
+                        }
+                    }
+                    name => Token::Command(name.to_string()),
+                }
+            }
+            c if c.is_alphabetic() => {
+                chars.next();
+                Token::Letter(c)
+            }
+            _ => {
+                chars.next();
+                match c {
+                    '+' => Token::Plus,
+                    '-' => Token::Minus,
+                    '*' => Token::Times,
+                    '/' => Token::Slash,
+                    '^' => Token::Caret,
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    '{' => Token::LBrace,
+                    '}' => Token::RBrace,
+                    '[' => Token::LBracket,
+                    ']' => Token::RBracket,
+                    _ => {
+                        return Err(ParseError {
+                            message: format!("unexpected character `{}`", c),
+                            position,
+                        })
+                    }
+                }
+            }
+        };
+        tokens.push((token, position));
+    }
+    tokens.push((Token::End, input.len()));
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{apply, pow, sin, x};
+    use crate::latex::{derivative_equation, parse_latex};
+    use crate::parse::{parse, parse_with_variable, ParseError};
+    use crate::{Derivative, Expression};
+
+    #[test]
+    fn test_to_latex() {
+        let cases = [
+            ("x", "x"),
+            ("-3", "-3"),
+            ("sin(x^2)", "\\sin\\left(x^{2}\\right)"),
+            ("exp(2x)", "e^{2x}"),
+            (
+                "log(x) + cos(x) - tan(x)",
+                "\\ln\\left(x\\right) + \\cos\\left(x\\right) - \\tan\\left(x\\right)",
+            ),
+            ("x^-1", "\\frac{1}{x}"),
+            ("2x / (x + 1)", "\\frac{2x}{x + 1}"),
+            ("-x / x^2", "-\\frac{x}{x^{2}}"),
+            ("(1 - x) / x", "\\frac{1 - x}{x}"),
+            ("2 * 3 * x", "2 \\cdot 3 \\cdot x"),
+            ("x * sin(x)", "x \\cdot \\sin\\left(x\\right)"),
+            ("x * (x - 1)", "x \\cdot \\left(x - 1\\right)"),
+            ("x * -2", "x \\cdot \\left(-2\\right)"),
+            ("x + (-x + 1)", "x + \\left(-x + 1\\right)"),
+            ("-(x + 1)", "-\\left(x + 1\\right)"),
+            ("x - (x + 1)", "x - \\left(x + 1\\right)"),
+            ("x - (1 - x)", "x - \\left(1 - x\\right)"),
+            ("(x + 1)^2", "\\left(x + 1\\right)^{2}"),
+            ("exp(x)^2", "\\left(e^{x}\\right)^{2}"),
+            ("sin(x)^3", "\\sin\\left(x\\right)^{3}"),
+        ];
+
+        for case in cases {
+            assert_eq!(parse(case.0).unwrap().to_latex(), case.1, "{}", case.0);
+        }
+    }
+
+    #[test]
+    fn test_to_latex_undefined() {
+        let f = apply("f", [x()]);
+        let cases = [
+            (
+                sin(f.clone()).diff(),
+                "\\cos\\left(f\\left(x\\right)\\right) \\cdot f'\\left(x\\right) \\cdot 1",
+            ),
+            (pow(f.clone(), 2), "f\\left(x\\right)^{2}"),
+            (
+                Derivative::new(f, 5, 0).unwrap().into(),
+                "f^{(5)}\\left(x\\right)",
+            ),
+            (
+                Derivative::new(apply("phi", [x(), x()]), 1, 1)
+                    .unwrap()
+                    .into(),
+                "\\operatorname{phi}^{(0,1)}\\left(x, x\\right)",
+            ),
+        ];
+
+        for case in cases {
+            let expression: Expression = case.0;
+            assert_eq!(expression.to_latex(), case.1);
+        }
+    }
+
+    #[test]
+    fn test_derivative_equation() {
+        let expression = parse_with_variable("t^3", "t").unwrap();
+        let derivative = parse_with_variable("3t^2", "t").unwrap();
+
+        assert_eq!(
+            derivative_equation(&expression, &derivative, 1, "t"),
+            "\\frac{d}{dt} \\left(t^{3}\\right) = 3t^{2}"
+        );
+        assert_eq!(
+            derivative_equation(
+                &expression,
+                &parse_with_variable("6t", "t").unwrap(),
+                2,
+                "t"
+            ),
+            "\\frac{d^{2}}{dt^{2}} \\left(t^{3}\\right) = 6t"
+        );
+    }
+
+    #[test]
+    fn test_parse_latex() {
+        let cases = [
+            ("\\sin(x^2) + \\frac{1}{x}", "sin(x^2) + 1 / x"),
+            ("e^{2x}", "exp(2x)"),
+            ("e^x e", "exp(x) exp(1)"),
+            ("2x \\cdot \\cos x", "2x * cos(x)"),
+            ("3 \\times \\ln{x}", "3 * log(x)"),
+            ("\\left( x + 1 \\right)^{-2}", "(x + 1)^-2"),
+            ("x^{10} - x^2", "x^10 - x^2"),
+            ("-\\tan^2 x", "-tan(x)^2"),
+            ("\\exp\\left(\\frac{x}{2}\\right)", "exp(x / 2)"),
+            ("\\sqrt{x}", "exp(log(x) / 2)"),
+            ("\\sqrt[3]{x + 1}", "exp(log(x + 1) / 3)"),
+            ("x\\,\\sin x", "x sin(x)"),
+            (
+                "\\frac{e^{x}}{x} - \\frac{e^{x}}{x^{2}}",
+                "exp(x) / x - exp(x) / x^2",
+            ),
+        ];
+
+        for case in cases {
+            assert_eq!(parse_latex(case.0), parse(case.1), "{}", case.0);
+        }
+    }
+
+    #[test]
+    fn test_parse_latex_error() {
+        let cases = [
+            ("\\sinh x", "unknown command `\\sinh`", 0),
+            (
+                "1 + \\frac{1}x",
+                "`\\frac` expects two `{...}` arguments",
+                4,
+            ),
+            ("\\sqrt[x]{x}", "`\\sqrt[n]` needs an integer n > 1", 0),
+            ("x^{y}", "unknown variable `y`", 3),
+            ("x^{x}", "exponent must be an integer", 2),
+            ("x^12", "exponents with several digits need braces", 2),
+            ("\\left x", "`\\left` must be followed by a parenthesis", 0),
+            ("\\frac{1}{x", "unclosed `{`", 8),
+            ("2 \\cos", "`\\cos` needs an argument", 2),
+        ];
+
+        for case in cases {
+            assert_eq!(
+                parse_latex(case.0),
+                Err(ParseError {
+                    message: case.1.to_string(),
+                    position: case.2,
+                }),
+                "{}",
+                case.0
+            );
+        }
+    }
+}
@@ -0,0 +1,675 @@
+pub mod build;
+pub mod checked;
+pub mod compile;
+mod display;
+pub mod domain;
+pub mod dot;
+pub mod equivalence;
+pub mod function;
+pub mod implicit;
+pub mod json;
+pub mod latex;
+pub mod limit;
+pub mod mathml;
+pub mod parse;
+pub mod polynomial;
+mod rng;
+pub mod schema;
+pub mod sexpr;
+mod simplify;
+pub mod solve;
+pub mod substitute;
+pub mod trace;
+pub mod verify;
+pub mod visit;
+
+use visit::Fold;
+
+pub type Scaler = isize;
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Base {
+    Scaler(Scaler),
+    X(X),
+    Exp(Exp),
+    Pow(Pow),
+    Log(Log),
+    Sin(Sin),
+    Cos(Cos),
+    Tan(Tan),
+    Custom(Custom),
+    Apply(Apply),
+    Derivative(Derivative),
+}
+
+impl Base {
+    pub fn diff(&self) -> Expression {
+        Differentiate.fold_base(self)
+    }
+
+    pub fn eval(&self, x: f64) -> f64 {
+        match self {
+            Self::Scaler(scaler) => *scaler as f64,
+            Self::X(_) => x,
+            Self::Exp(exp) => exp.eval(x),
+            Self::Pow(pow) => pow.eval(x),
+            Self::Log(log) => log.eval(x),
+            Self::Sin(sin) => sin.eval(x),
+            Self::Cos(cos) => cos.eval(x),
+            Self::Tan(tan) => tan.eval(x),
+            Self::Custom(custom) => custom.eval(x),
+            Self::Apply(apply) => apply.eval(x),
+            Self::Derivative(derivative) => derivative.eval(x),
+        }
+    }
+}
+
+impl From<Base> for Expression {
+    fn from(base: Base) -> Self {
+        Expression::Base(base)
+    }
+}
+
+pub type Add = Vec<Expression>;
+pub type Mul = Vec<Expression>;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Expression {
+    Base(Base),
+    Add(Add),
+    Mul(Mul),
+}
+
+impl Expression {
+    /// # Panics
+    ///
+    /// If an exponent or a derivative order would overflow; `try_diff`
+    /// reports these as errors instead.
+    pub fn diff(&self) -> Expression {
+        Differentiate.fold_expression(self)
+    }
+
+    pub fn eval(&self, x: f64) -> f64 {
+        match self {
+            Self::Base(base) => base.eval(x),
+            Self::Add(add) => add.iter().map(|e| e.eval(x)).sum(),
+            Self::Mul(mul) => mul.iter().map(|e| e.eval(x)).product(),
+        }
+    }
+}
+
+// Differentiation as a `Fold`: `Add` keeps the default term-by-term
+// recursion, `Mul` applies the product rule and every function the chain rule.
+struct Differentiate;
+
+impl Fold for Differentiate {
+    fn fold_mul(&mut self, factors: &[Expression]) -> Expression {
+        diff_mul(self, factors)
+    }
+
+    fn fold_base(&mut self, base: &Base) -> Expression {
+        diff_base(self, base)
+    }
+}
+
+// The product rule. The rules take the `Fold` that differentiates the
+// operands, so that `diff_with_trace` can wrap `Differentiate`.
+pub(crate) fn diff_mul<F: Fold + ?Sized>(folder: &mut F, factors: &[Expression]) -> Expression {
+    let mut expressions = vec![];
+    for i in 0..factors.len() {
+        let mut row = vec![];
+        for (j, expression) in factors.iter().enumerate() {
+            if i == j {
+                row.push(folder.fold_expression(expression))
+            } else {
+                row.push(expression.clone())
+            }
+        }
+        expressions.push(Expression::Mul(row));
+    }
+    Expression::Add(expressions)
+}
+
+// The derivative of a single node: the chain rule for functions.
+pub(crate) fn diff_base<F: Fold + ?Sized>(folder: &mut F, base: &Base) -> Expression {
+    let (mut factors, arg) = match base {
+        Base::Scaler(_) => return Expression::Base(Base::Scaler(0)),
+        Base::X(_) => return Expression::Base(Base::Scaler(1)),
+        Base::Apply(_) | Base::Derivative(_) => {
+            return partials(base, |arg| folder.fold_expression(arg))
+                .unwrap_or_else(|error| panic!("{}", error))
+        }
+        Base::Exp(exp) => (vec![Expression::Base(Base::Exp(exp.clone()))], &exp.arg),
+        Base::Pow(pow) => (
+            vec![
+                Expression::Base(Base::Scaler(pow.exp)),
+                Expression::Base(Base::Pow(Pow {
+                    exp: pow.exp - 1,
+                    arg: pow.arg.clone(),
+                })),
+            ],
+            &pow.arg,
+        ),
+        Base::Log(log) => (
+            vec![Expression::Base(Base::Pow(Pow {
+                exp: -1,
+                arg: log.arg.clone(),
+            }))],
+            &log.arg,
+        ),
+        Base::Sin(sin) => (
+            vec![Expression::Base(Base::Cos(Cos {
+                arg: sin.arg.clone(),
+            }))],
+            &sin.arg,
+        ),
+        Base::Cos(cos) => (
+            vec![
+                Expression::Base(Base::Scaler(-1)),
+                Expression::Base(Base::Sin(Sin {
+                    arg: cos.arg.clone(),
+                })),
+            ],
+            &cos.arg,
+        ),
+        Base::Tan(tan) => (
+            vec![Expression::Base(Base::Pow(Pow {
+                exp: -2,
+                arg: Box::new(Expression::Base(Base::Cos(Cos {
+                    arg: tan.arg.clone(),
+                }))),
+            }))],
+            &tan.arg,
+        ),
+        Base::Custom(custom) => (vec![custom.outer_derivative()], &custom.arg),
+    };
+    factors.push(folder.fold_expression(arg));
+    Expression::Mul(factors)
+}
+
+// The chain rule for a call of an undefined function, or of one of its
+// derivatives: a partial derivative times `diff(arg)` for every argument.
+fn partials(
+    function: &Base,
+    mut diff: impl FnMut(&Expression) -> Expression,
+) -> Result<Expression, checked::DiffError> {
+    let (args, orders) = match function {
+        Base::Apply(apply) => (&apply.args, vec![0; apply.args.len()]),
+        Base::Derivative(derivative) => match derivative.check() {
+            Ok(apply) => (&apply.args, derivative.orders()),
+            Err(message) => return Err(checked::DiffError::Unsupported(message)),
+        },
+        _ => unreachable!("`{}` is defined", function),
+    };
+    let mut terms = vec![];
+    for (i, arg) in args.iter().enumerate() {
+        // The partial derivative is one order higher in argument `i`.
+        if orders[i].checked_add(1).is_none() {
+            return Err(checked::DiffError::OrderOverflow { order: orders[i] });
+        }
+        let partial = match function {
+            // `order` is at most `orders[i]`, so this cannot overflow.
+            Base::Derivative(derivative) if derivative.wrt == i => Derivative {
+                func: derivative.func.clone(),
+                order: derivative.order + 1,
+                wrt: i,
+            },
+            function => Derivative {
+                func: Box::new(Expression::Base(function.clone())),
+                order: 1,
+                wrt: i,
+            },
+        };
+        terms.push(Expression::Mul(vec![partial.into(), diff(arg)]));
+    }
+    Ok(if terms.len() == 1 {
+        terms.remove(0)
+    } else {
+        Expression::Add(terms)
+    })
+}
+
+pub trait DiffBase {
+    fn diff(&self) -> Expression;
+}
+
+pub trait EvalBase {
+    fn eval(&self, x: f64) -> f64;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct X {}
+
+impl From<X> for Expression {
+    fn from(x: X) -> Self {
+        Expression::Base(Base::X(x))
+    }
+}
+
+impl DiffBase for X {
+    fn diff(&self) -> Expression {
+        Base::X(self.clone()).diff()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Exp {
+    pub arg: Box<Expression>,
+}
+
+impl Exp {
+    pub fn new(arg: Expression) -> Self {
+        Self { arg: Box::new(arg) }
+    }
+}
+
+impl From<Exp> for Expression {
+    fn from(exp: Exp) -> Self {
+        Expression::Base(Base::Exp(exp))
+    }
+}
+
+impl DiffBase for Exp {
+    fn diff(&self) -> Expression {
+        Base::Exp(self.clone()).diff()
+    }
+}
+
+impl EvalBase for Exp {
+    fn eval(&self, x: f64) -> f64 {
+        self.arg.eval(x).exp()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pow {
+    pub exp: isize,
+    pub arg: Box<Expression>,
+}
+
+impl Pow {
+    pub fn new(exp: isize, arg: Expression) -> Self {
+        Self {
+            exp,
+            arg: Box::new(arg),
+        }
+    }
+}
+
+impl From<Pow> for Expression {
+    fn from(pow: Pow) -> Self {
+        Expression::Base(Base::Pow(pow))
+    }
+}
+
+impl DiffBase for Pow {
+    fn diff(&self) -> Expression {
+        Base::Pow(self.clone()).diff()
+    }
+}
+
+impl EvalBase for Pow {
+    fn eval(&self, x: f64) -> f64 {
+        self.arg.eval(x).powf(self.exp as f64)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Log {
+    pub arg: Box<Expression>,
+}
+
+impl Log {
+    pub fn new(arg: Expression) -> Self {
+        Self { arg: Box::new(arg) }
+    }
+}
+
+impl From<Log> for Expression {
+    fn from(log: Log) -> Self {
+        Expression::Base(Base::Log(log))
+    }
+}
+
+impl DiffBase for Log {
+    fn diff(&self) -> Expression {
+        Base::Log(self.clone()).diff()
+    }
+}
+
+impl EvalBase for Log {
+    fn eval(&self, x: f64) -> f64 {
+        self.arg.eval(x).ln()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Sin {
+    pub arg: Box<Expression>,
+}
+
+impl Sin {
+    pub fn new(arg: Expression) -> Self {
+        Self { arg: Box::new(arg) }
+    }
+}
+
+impl From<Sin> for Expression {
+    fn from(sin: Sin) -> Self {
+        Expression::Base(Base::Sin(sin))
+    }
+}
+
+impl DiffBase for Sin {
+    fn diff(&self) -> Expression {
+        Base::Sin(self.clone()).diff()
+    }
+}
+
+impl EvalBase for Sin {
+    fn eval(&self, x: f64) -> f64 {
+        self.arg.eval(x).sin()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cos {
+    pub arg: Box<Expression>,
+}
+
+impl Cos {
+    pub fn new(arg: Expression) -> Self {
+        Self { arg: Box::new(arg) }
+    }
+}
+
+impl From<Cos> for Expression {
+    fn from(cos: Cos) -> Self {
+        Expression::Base(Base::Cos(cos))
+    }
+}
+
+impl DiffBase for Cos {
+    fn diff(&self) -> Expression {
+        Base::Cos(self.clone()).diff()
+    }
+}
+
+impl EvalBase for Cos {
+    fn eval(&self, x: f64) -> f64 {
+        self.arg.eval(x).cos()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Tan {
+    pub arg: Box<Expression>,
+}
+
+impl Tan {
+    pub fn new(arg: Expression) -> Self {
+        Self { arg: Box::new(arg) }
+    }
+}
+
+impl From<Tan> for Expression {
+    fn from(tan: Tan) -> Self {
+        Expression::Base(Base::Tan(tan))
+    }
+}
+
+impl DiffBase for Tan {
+    fn diff(&self) -> Expression {
+        Base::Tan(self.clone()).diff()
+    }
+}
+
+impl EvalBase for Tan {
+    fn eval(&self, x: f64) -> f64 {
+        self.arg.eval(x).tan()
+    }
+}
+
+/// A call to a function added with `function::register`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Custom {
+    pub name: String,
+    pub arg: Box<Expression>,
+}
+
+impl Custom {
+    pub fn new(name: impl Into<String>, arg: Expression) -> Self {
+        Self {
+            name: name.into(),
+            arg: Box::new(arg),
+        }
+    }
+}
+
+impl From<Custom> for Expression {
+    fn from(custom: Custom) -> Self {
+        Expression::Base(Base::Custom(custom))
+    }
+}
+
+impl DiffBase for Custom {
+    fn diff(&self) -> Expression {
+        Base::Custom(self.clone()).diff()
+    }
+}
+
+impl EvalBase for Custom {
+    /// `NaN` if no function of this name is registered.
+    fn eval(&self, x: f64) -> f64 {
+        match function::lookup(&self.name) {
+            Some(function) => function.eval(self.arg.eval(x)),
+            None => f64::NAN,
+        }
+    }
+}
+
+/// A call to a function without a definition, such as `f(x)`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Apply {
+    pub name: String,
+    pub args: Vec<Expression>,
+}
+
+impl Apply {
+    pub fn new(name: impl Into<String>, args: Vec<Expression>) -> Self {
+        Self {
+            name: name.into(),
+            args,
+        }
+    }
+}
+
+impl From<Apply> for Expression {
+    fn from(apply: Apply) -> Self {
+        Expression::Base(Base::Apply(apply))
+    }
+}
+
+impl DiffBase for Apply {
+    fn diff(&self) -> Expression {
+        Base::Apply(self.clone()).diff()
+    }
+}
+
+impl EvalBase for Apply {
+    /// Always `NaN`, as the function has no definition.
+    fn eval(&self, _: f64) -> f64 {
+        f64::NAN
+    }
+}
+
+/// The unevaluated `order`-th derivative of `func` with respect to its
+/// argument number `wrt`, taken at the same arguments. `func` is an `Apply`,
+/// or another `Derivative` for mixed partial derivatives.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Derivative {
+    func: Box<Expression>,
+    order: usize,
+    wrt: usize,
+}
+
+impl Derivative {
+    /// Fails with the message from `check` if `func` is not a function, has
+    /// no argument `wrt`, or the orders overflow.
+    pub fn new(func: Expression, order: usize, wrt: usize) -> Result<Self, String> {
+        let derivative = Self {
+            func: Box::new(func),
+            order,
+            wrt,
+        };
+        derivative.check()?;
+        Ok(derivative)
+    }
+
+    pub fn func(&self) -> &Expression {
+        &self.func
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    pub fn wrt(&self) -> usize {
+        self.wrt
+    }
+
+    /// The call at the bottom of `func`, or `None` if `func` is not a
+    /// function.
+    pub fn apply(&self) -> Option<&Apply> {
+        match &*self.func {
+            Expression::Base(Base::Apply(apply)) => Some(apply),
+            Expression::Base(Base::Derivative(derivative)) => derivative.apply(),
+            _ => None,
+        }
+    }
+
+    /// Like `apply`, but also checks that every `wrt` on the way down names
+    /// an argument of the call and that the orders add up without
+    /// overflowing. The error describes the first problem.
+    pub fn check(&self) -> Result<&Apply, String> {
+        let apply = match &*self.func {
+            Expression::Base(Base::Apply(apply)) => apply,
+            Expression::Base(Base::Derivative(derivative)) => derivative.check()?,
+            func => {
+                return Err(format!(
+                    "the derivative of `{}`, which is not a function",
+                    func
+                ))
+            }
+        };
+        if self.wrt >= apply.args.len() {
+            return Err(format!(
+                "the derivative of `{}` with respect to argument {}, which it does not have",
+                apply.name, self.wrt
+            ));
+        }
+        match self.checked_orders() {
+            Some(_) => Ok(apply),
+            None => Err(format!(
+                "the derivative of `{}` with respect to argument {} of an order above {}",
+                apply.name,
+                self.wrt,
+                usize::MAX
+            )),
+        }
+    }
+
+    /// How often the function is differentiated with respect to each of its
+    /// arguments.
+    ///
+    /// # Panics
+    ///
+    /// If an order does not fit in a `usize`, which `check` reports.
+    pub fn orders(&self) -> Vec<usize> {
+        self.checked_orders()
+            .unwrap_or_else(|| panic!("the orders of `{}` overflow", self.func))
+    }
+
+    fn checked_orders(&self) -> Option<Vec<usize>> {
+        let mut orders = match &*self.func {
+            Expression::Base(Base::Derivative(derivative)) => derivative.checked_orders()?,
+            _ => vec![0; self.apply().map_or(0, |apply| apply.args.len())],
+        };
+        if let Some(order) = orders.get_mut(self.wrt) {
+            *order = order.checked_add(self.order)?;
+        }
+        Some(orders)
+    }
+}
+
+impl From<Derivative> for Expression {
+    fn from(derivative: Derivative) -> Self {
+        Expression::Base(Base::Derivative(derivative))
+    }
+}
+
+impl DiffBase for Derivative {
+    fn diff(&self) -> Expression {
+        Base::Derivative(self.clone()).diff()
+    }
+}
+
+impl EvalBase for Derivative {
+    /// Always `NaN`, as the function has no definition.
+    fn eval(&self, _: f64) -> f64 {
+        f64::NAN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{apply, sin, x};
+    use crate::{Derivative, Expression};
+
+    #[test]
+    fn test_diff() {
+        let golden = include_str!("../tests/golden/diff.sexpr");
+        let lines: Vec<&str> = golden
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with(';'))
+            .collect();
+        assert_eq!(lines.len() % 2, 0);
+
+        for case in lines.chunks(2) {
+            let expression = Expression::from_sexpr(case[0]).unwrap();
+            let derivative = Expression::from_sexpr(case[1]).unwrap();
+            assert_eq!(expression.diff(), derivative, "{}", case[0]);
+            assert_eq!(derivative.to_sexpr(), case[1]);
+        }
+    }
+
+    #[test]
+    fn test_derivative_new() {
+        let f = apply("f", [x(), x()]);
+        let cases = [
+            (sin(x()), 1, 0, Err("the derivative of `sin(x)`, which is not a function")),
+            (
+                f.clone(),
+                1,
+                2,
+                Err("the derivative of `f` with respect to argument 2, which it does not have"),
+            ),
+            (
+                Derivative::new(f.clone(), usize::MAX, 0).unwrap().into(),
+                1,
+                0,
+                Err("the derivative of `f` with respect to argument 0 of an order above 18446744073709551615"),
+            ),
+            (
+                Derivative::new(f.clone(), usize::MAX, 0).unwrap().into(),
+                1,
+                1,
+                Ok(vec![usize::MAX, 1]),
+            ),
+        ];
+
+        for case in cases {
+            let orders = Derivative::new(case.0, case.1, case.2).map(|d| d.orders());
+            assert_eq!(orders, case.3.map_err(str::to_string));
+        }
+    }
+}
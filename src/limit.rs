@@ -0,0 +1,320 @@
+use std::fmt;
+
+use crate::function::lookup;
+use crate::{Base, Expression, Pow};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Point {
+    Finite(f64),
+    PosInfinity,
+    NegInfinity,
+}
+
+impl Point {
+    fn value(self) -> f64 {
+        match self {
+            Self::Finite(x) => x,
+            Self::PosInfinity => f64::INFINITY,
+            Self::NegInfinity => f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl From<f64> for Point {
+    fn from(x: f64) -> Self {
+        if x == f64::INFINITY {
+            Self::PosInfinity
+        } else if x == f64::NEG_INFINITY {
+            Self::NegInfinity
+        } else {
+            Self::Finite(x)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LimitOptions {
+    /// How many times L'Hôpital's rule may be applied to a single quotient.
+    pub max_iterations: usize,
+    /// Values whose magnitude is at most `epsilon` are treated as zero.
+    pub epsilon: f64,
+}
+
+impl Default for LimitOptions {
+    fn default() -> Self {
+        Self {
+            max_iterations: 8,
+            epsilon: 1e-12,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LimitError {
+    /// L'Hôpital's rule was applied `max_iterations` times and the quotient
+    /// is still indeterminate.
+    IterationLimit(usize),
+    /// An indeterminate form that is not a quotient, such as `0 * ∞`.
+    Indeterminate(&'static str),
+    /// A non-zero numerator over a denominator that tends to zero.
+    Unbounded,
+    /// The function oscillates, e.g. `sin` at infinity.
+    Oscillates(&'static str),
+    /// The argument of the function leaves its domain, e.g. `log` of a negative number.
+    Domain(&'static str),
+    /// The expression calls a function without a definition.
+    Undefined(String),
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IterationLimit(n) => write!(
+                f,
+                "quotient is still indeterminate after applying L'Hôpital's rule {} times",
+                n
+            ),
+            Self::Indeterminate(form) => {
+                write!(f, "indeterminate form `{}` is not a quotient", form)
+            }
+            Self::Unbounded => write!(f, "denominator tends to zero but numerator does not"),
+            Self::Oscillates(name) => write!(f, "`{}` oscillates and has no limit", name),
+            Self::Domain(name) => write!(f, "argument leaves the domain of `{}`", name),
+            Self::Undefined(name) => write!(f, "`{}` has no definition", name),
+        }
+    }
+}
+
+impl std::error::Error for LimitError {}
+
+impl Expression {
+    pub fn limit(&self, point: Point) -> Result<f64, LimitError> {
+        self.limit_with(point, &LimitOptions::default())
+    }
+
+    pub fn limit_with(&self, point: Point, options: &LimitOptions) -> Result<f64, LimitError> {
+        Limit {
+            x: point.value(),
+            options,
+        }
+        .expression(self, 0)
+    }
+}
+
+struct Limit<'a> {
+    x: f64,
+    options: &'a LimitOptions,
+}
+
+impl Limit<'_> {
+    fn is_zero(&self, value: f64) -> bool {
+        value.abs() <= self.options.epsilon
+    }
+
+    fn expression(&self, expression: &Expression, iterations: usize) -> Result<f64, LimitError> {
+        if let Some((numerator, denominator)) = as_quotient(expression) {
+            return self.quotient(numerator, denominator, iterations);
+        }
+        match expression {
+            Expression::Base(base) => self.base(base, iterations),
+            Expression::Add(add) => {
+                let mut sum: f64 = 0.0;
+                for term in add {
+                    let value = self.expression(term, iterations)?;
+                    if sum.is_infinite() && value.is_infinite() && sum.signum() != value.signum() {
+                        return Err(LimitError::Indeterminate("∞ - ∞"));
+                    }
+                    sum += value;
+                }
+                Ok(sum)
+            }
+            Expression::Mul(mul) => {
+                if mul.contains(&Expression::Base(Base::Scaler(0))) {
+                    return Ok(0.0);
+                }
+                let mut product: f64 = 1.0;
+                for factor in mul {
+                    let value = self.expression(factor, iterations)?;
+                    if (self.is_zero(product) && value.is_infinite())
+                        || (product.is_infinite() && self.is_zero(value))
+                    {
+                        return Err(LimitError::Indeterminate("0 * ∞"));
+                    }
+                    product *= value;
+                }
+                Ok(product)
+            }
+        }
+    }
+
+    fn base(&self, base: &Base, iterations: usize) -> Result<f64, LimitError> {
+        match base {
+            Base::Scaler(scaler) => Ok(*scaler as f64),
+            Base::X(_) => Ok(self.x),
+            Base::Exp(exp) => Ok(self.expression(&exp.arg, iterations)?.exp()),
+            Base::Pow(pow) => Ok(self.expression(&pow.arg, iterations)?.powf(pow.exp as f64)),
+            Base::Log(log) => {
+                let arg = self.expression(&log.arg, iterations)?;
+                if self.is_zero(arg) {
+                    Ok(f64::NEG_INFINITY)
+                } else if arg < 0.0 {
+                    Err(LimitError::Domain("log"))
+                } else {
+                    Ok(arg.ln())
+                }
+            }
+            Base::Sin(sin) => {
+                let arg = self.expression(&sin.arg, iterations)?;
+                if arg.is_infinite() {
+                    return Err(LimitError::Oscillates("sin"));
+                }
+                Ok(arg.sin())
+            }
+            Base::Cos(cos) => {
+                let arg = self.expression(&cos.arg, iterations)?;
+                if arg.is_infinite() {
+                    return Err(LimitError::Oscillates("cos"));
+                }
+                Ok(arg.cos())
+            }
+            Base::Tan(tan) => {
+                let arg = self.expression(&tan.arg, iterations)?;
+                if arg.is_infinite() {
+                    return Err(LimitError::Oscillates("tan"));
+                }
+                if self.is_zero(arg.cos()) {
+                    return Err(LimitError::Unbounded);
+                }
+                Ok(arg.tan())
+            }
+            Base::Apply(apply) => Err(LimitError::Undefined(apply.name.clone())),
+            Base::Derivative(derivative) => Err(LimitError::Undefined(
+                derivative
+                    .apply()
+                    .map_or_else(|| derivative.func.to_string(), |apply| apply.name.clone()),
+            )),
+            Base::Custom(custom) => {
+                let function = lookup(&custom.name)
+                    .ok_or_else(|| LimitError::Undefined(custom.name.clone()))?;
+                let value = function.eval(self.expression(&custom.arg, iterations)?);
+                if value.is_nan() {
+                    return Err(LimitError::Domain(function.name));
+                }
+                Ok(value)
+            }
+        }
+    }
+
+    fn quotient(
+        &self,
+        numerator: Expression,
+        denominator: Expression,
+        iterations: usize,
+    ) -> Result<f64, LimitError> {
+        let n = self.expression(&numerator, iterations)?;
+        let d = self.expression(&denominator, iterations)?;
+        let indeterminate =
+            (self.is_zero(n) && self.is_zero(d)) || (n.is_infinite() && d.is_infinite());
+        if indeterminate {
+            if iterations >= self.options.max_iterations {
+                return Err(LimitError::IterationLimit(iterations));
+            }
+            return self.quotient(numerator.diff(), denominator.diff(), iterations + 1);
+        }
+        if self.is_zero(d) {
+            return Err(LimitError::Unbounded);
+        }
+        Ok(n / d)
+    }
+}
+
+// Splits `a * b^-1 * c^-2` into `(a, b * c^2)`, or returns `None` when there
+// is nothing to divide by.
+fn as_quotient(expression: &Expression) -> Option<(Expression, Expression)> {
+    let factors = match expression {
+        Expression::Base(Base::Pow(_)) => std::slice::from_ref(expression),
+        Expression::Mul(mul) => mul.as_slice(),
+        _ => return None,
+    };
+    let mut numerator = vec![];
+    let mut denominator = vec![];
+    for factor in factors {
+        match factor {
+            Expression::Base(Base::Pow(pow)) if pow.exp < 0 && pow.exp != isize::MIN => {
+                if pow.exp == -1 {
+                    denominator.push(*pow.arg.clone());
+                } else {
+                    denominator.push(Expression::Base(Base::Pow(Pow {
+                        exp: -pow.exp,
+                        arg: pow.arg.clone(),
+                    })));
+                }
+            }
+            _ => numerator.push(factor.clone()),
+        }
+    }
+    if denominator.is_empty() {
+        return None;
+    }
+    Some((product(numerator), product(denominator)))
+}
+
+fn product(mut factors: Vec<Expression>) -> Expression {
+    match factors.len() {
+        0 => Expression::Base(Base::Scaler(1)),
+        1 => factors.remove(0),
+        _ => Expression::Mul(factors),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{cos, custom, exp, log, pow, sin, x};
+    use crate::limit::{LimitError, LimitOptions, Point};
+
+    #[test]
+    fn test_limit() {
+        let cases = [
+            (pow(x(), 2), Point::Finite(3.0), Ok(9.0)),
+            (sin(x()) / x(), Point::Finite(0.0), Ok(1.0)),
+            ((1 - cos(x())) * pow(x(), -2), Point::Finite(0.0), Ok(0.5)),
+            (x() / exp(x()), Point::PosInfinity, Ok(0.0)),
+            (log(x()) / x(), Point::PosInfinity, Ok(0.0)),
+            (exp(x()), Point::NegInfinity, Ok(0.0)),
+            (pow(x(), -1), Point::Finite(0.0), Err(LimitError::Unbounded)),
+            (
+                sin(x()),
+                Point::PosInfinity,
+                Err(LimitError::Oscillates("sin")),
+            ),
+            (
+                x() * log(x()),
+                Point::Finite(0.0),
+                Err(LimitError::Indeterminate("0 * ∞")),
+            ),
+            (
+                custom("unregistered", x()) / x(),
+                Point::Finite(0.0),
+                Err(LimitError::Undefined("unregistered".to_string())),
+            ),
+        ];
+
+        for case in cases {
+            assert_eq!(case.0.limit(case.1), case.2);
+        }
+    }
+
+    #[test]
+    fn test_limit_iteration_limit() {
+        let expression = (1 - cos(x())) * pow(x(), -2);
+        let options = LimitOptions {
+            max_iterations: 1,
+            ..LimitOptions::default()
+        };
+
+        assert_eq!(
+            expression.limit_with(Point::Finite(0.0), &options),
+            Err(LimitError::IterationLimit(1))
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use crate::display::negated;
+use crate::{Base, Expression, Pow};
+
+const FUNCTION_APPLICATION: &str = "<mo>&#x2061;</mo>";
+const INVISIBLE_TIMES: &str = "<mo>&#x2062;</mo>";
+const DOT: &str = "<mo>&#x22c5;</mo>";
+const PRIME: &str = "&#x2032;";
+
+impl Expression {
+    /// Renders the expression as a presentation MathML `<math>` element.
+    pub fn to_mathml(&self) -> String {
+        self.to_mathml_with_variable("x")
+    }
+
+    /// Like `to_mathml`, but the variable is written as `variable`.
+    pub fn to_mathml_with_variable(&self, variable: &str) -> String {
+        format!(
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\">{}</math>",
+            Printer { variable }.expression(self)
+        )
+    }
+}
+
+// Every method returns exactly one element, so results can be used as the
+// children of `<msup>` and `<mfrac>`.
+struct Printer<'a> {
+    variable: &'a str,
+}
+
+impl Printer<'_> {
+    fn expression(&self, expression: &Expression) -> String {
+        match expression {
+            Expression::Base(base) => self.base(base),
+            Expression::Add(add) => self.add(add),
+            Expression::Mul(mul) => self.mul(mul),
+        }
+    }
+
+    fn base(&self, base: &Base) -> String {
+        match base {
+            Base::Scaler(scaler) if *scaler < 0 => {
+                format!("<mrow><mo>-</mo><mn>{}</mn></mrow>", scaler.unsigned_abs())
+            }
+            Base::Scaler(scaler) => format!("<mn>{}</mn>", scaler),
+            Base::X(_) => format!("<mi>{}</mi>", escape(self.variable)),
+            Base::Exp(exp) => format!("<msup><mi>e</mi>{}</msup>", self.expression(&exp.arg)),
+            Base::Pow(pow) if pow.exp < 0 && pow.exp != isize::MIN => {
+                self.fraction(&[], &[reciprocal(pow)])
+            }
+            Base::Pow(pow) => format!(
+                "<msup>{}{}</msup>",
+                self.pow_base(&pow.arg),
+                self.base(&Base::Scaler(pow.exp))
+            ),
+            Base::Log(log) => self.function("ln", &log.arg),
+            Base::Sin(sin) => self.function("sin", &sin.arg),
+            Base::Cos(cos) => self.function("cos", &cos.arg),
+            Base::Tan(tan) => self.function("tan", &tan.arg),
+            Base::Custom(custom) => self.function(&escape(&custom.name), &custom.arg),
+            Base::Apply(apply) => {
+                self.call(&format!("<mi>{}</mi>", escape(&apply.name)), &apply.args)
+            }
+            Base::Derivative(derivative) => match derivative.apply() {
+                Some(apply) => {
+                    let name = format!("<mi>{}</mi>", escape(&apply.name));
+                    let marks = match derivative.orders()[..] {
+                        [order] if order <= 3 => format!("<mo>{}</mo>", PRIME.repeat(order)),
+                        ref orders => format!(
+                            "<mrow><mo>(</mo>{}<mo>)</mo></mrow>",
+                            orders
+                                .iter()
+                                .map(|order| format!("<mn>{}</mn>", order))
+                                .collect::<Vec<_>>()
+                                .join("<mo>,</mo>")
+                        ),
+                    };
+                    self.call(&format!("<msup>{}{}</msup>", name, marks), &apply.args)
+                }
+                None => format!(
+                    "<mrow><msubsup><mo>&#x2202;</mo><mn>{}</mn><mn>{}</mn></msubsup>{}</mrow>",
+                    derivative.wrt,
+                    derivative.order,
+                    self.parenthesized(&derivative.func)
+                ),
+            },
+        }
+    }
+
+    // `name` is already markup.
+    fn call(&self, name: &str, args: &[Expression]) -> String {
+        let args: Vec<String> = args.iter().map(|arg| self.expression(arg)).collect();
+        format!(
+            "<mrow>{}{}<mrow><mo>(</mo>{}<mo>)</mo></mrow></mrow>",
+            name,
+            FUNCTION_APPLICATION,
+            args.join("<mo>,</mo>")
+        )
+    }
+
+    fn function(&self, name: &str, arg: &Expression) -> String {
+        format!(
+            "<mrow><mi>{}</mi>{}{}</mrow>",
+            name,
+            FUNCTION_APPLICATION,
+            self.parenthesized(arg)
+        )
+    }
+
+    fn parenthesized(&self, expression: &Expression) -> String {
+        format!(
+            "<mrow><mo>(</mo>{}<mo>)</mo></mrow>",
+            self.expression(expression)
+        )
+    }
+
+    fn pow_base(&self, arg: &Expression) -> String {
+        match arg {
+            Expression::Base(Base::Scaler(scaler)) if *scaler >= 0 => self.expression(arg),
+            Expression::Base(
+                Base::X(_)
+                | Base::Log(_)
+                | Base::Sin(_)
+                | Base::Cos(_)
+                | Base::Tan(_)
+                | Base::Custom(_)
+                | Base::Apply(_)
+                | Base::Derivative(_),
+            ) => self.expression(arg),
+            Expression::Mul(mul) if mul.len() == 1 => self.pow_base(&mul[0]),
+            Expression::Add(add) if add.len() == 1 => self.pow_base(&add[0]),
+            _ => self.parenthesized(arg),
+        }
+    }
+
+    fn add(&self, add: &[Expression]) -> String {
+        match add {
+            [] => return "<mn>0</mn>".to_string(),
+            [term] => return self.expression(term),
+            _ => {}
+        }
+        let mut mathml = "<mrow>".to_string();
+        for (i, term) in add.iter().enumerate() {
+            if i == 0 {
+                mathml.push_str(&self.expression(term));
+                continue;
+            }
+            let (operator, term) = match negated(term) {
+                Some(negated) => ("-", negated),
+                None => ("+", term.clone()),
+            };
+            mathml.push_str(&format!("<mo>{}</mo>", operator));
+            mathml.push_str(&match &term {
+                Expression::Add(add)
+                    if add.len() > 1 && (operator == "-" || negated(&add[0]).is_some()) =>
+                {
+                    self.parenthesized(&term)
+                }
+                _ => self.expression(&term),
+            });
+        }
+        mathml.push_str("</mrow>");
+        mathml
+    }
+
+    fn mul(&self, mul: &[Expression]) -> String {
+        let mut numerator = vec![];
+        let mut denominator = vec![];
+        for factor in mul {
+            match factor {
+                Expression::Base(Base::Pow(pow)) if pow.exp < 0 && pow.exp != isize::MIN => {
+                    denominator.push(reciprocal(pow))
+                }
+                factor => numerator.push(factor.clone()),
+            }
+        }
+        if denominator.is_empty() {
+            return self.factors(&numerator);
+        }
+        self.fraction(&numerator, &denominator)
+    }
+
+    fn fraction(&self, numerator: &[Expression], denominator: &[Expression]) -> String {
+        let numerator = match numerator {
+            [factor] => factor.clone(),
+            factors => Expression::Mul(factors.to_vec()),
+        };
+        // Pull a leading minus sign out of the fraction, unless it belongs to
+        // the first term of a sum.
+        let (sign, numerator) = match negated(&numerator) {
+            Some(positive) => (true, positive),
+            None => (false, numerator),
+        };
+        let numerator = match &numerator {
+            Expression::Mul(factors) => self.factors(factors),
+            numerator => self.expression(numerator),
+        };
+        let fraction = format!("<mfrac>{}{}</mfrac>", numerator, self.factors(denominator));
+        if sign {
+            format!("<mrow><mo>-</mo>{}</mrow>", fraction)
+        } else {
+            fraction
+        }
+    }
+
+    // Uses invisible times after a leading integer coefficient, as in `2x`,
+    // and a dot operator between every other pair of factors.
+    fn factors(&self, factors: &[Expression]) -> String {
+        let (sign, factors) = match factors {
+            [Expression::Base(Base::Scaler(-1)), rest @ ..] if !rest.is_empty() => (true, rest),
+            factors => (false, factors),
+        };
+        let mut mathml = match factors {
+            [] => "<mn>1</mn>".to_string(),
+            [factor @ Expression::Add(add)] if sign && add.len() > 1 => self.parenthesized(factor),
+            [factor] => self.expression(factor),
+            _ => {
+                let mut mathml = "<mrow>".to_string();
+                for (i, factor) in factors.iter().enumerate() {
+                    if i > 0 {
+                        let coefficient =
+                            matches!(factors[i - 1], Expression::Base(Base::Scaler(_)));
+                        let numeric = matches!(factor, Expression::Base(Base::Scaler(_)));
+                        mathml.push_str(if i == 1 && coefficient && !numeric {
+                            INVISIBLE_TIMES
+                        } else {
+                            DOT
+                        });
+                    }
+                    mathml.push_str(&match factor {
+                        Expression::Add(add) if add.len() != 1 => self.parenthesized(factor),
+                        _ if i > 0 && negated(factor).is_some() => self.parenthesized(factor),
+                        _ => self.expression(factor),
+                    });
+                }
+                mathml.push_str("</mrow>");
+                mathml
+            }
+        };
+        if sign {
+            mathml = format!("<mrow><mo>-</mo>{}</mrow>", mathml);
+        }
+        mathml
+    }
+}
+
+fn reciprocal(pow: &Pow) -> Expression {
+    if pow.exp == -1 {
+        *pow.arg.clone()
+    } else {
+        Expression::Base(Base::Pow(Pow {
+            exp: -pow.exp,
+            arg: pow.arg.clone(),
+        }))
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{pow, sin, x};
+    use crate::parse::parse;
+    use crate::Derivative;
+
+    // Checks that tags nest properly and that every `&` starts an entity.
+    fn well_formed(xml: &str) -> bool {
+        let mut open = vec![];
+        let mut rest = xml;
+        while let Some(start) = rest.find(['<', '&']) {
+            if rest[..start].contains('>') {
+                return false;
+            }
+            let entity = rest[start..].starts_with('&');
+            rest = &rest[start + 1..];
+            let end = match rest.find(if entity { ';' } else { '>' }) {
+                Some(end) => end,
+                None => return false,
+            };
+            let token = &rest[..end];
+            rest = &rest[end + 1..];
+            if entity {
+                let valid = match token.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).is_ok(),
+                    None => matches!(token, "amp" | "lt" | "gt" | "quot" | "apos"),
+                };
+                if !valid {
+                    return false;
+                }
+            } else if let Some(name) = token.strip_prefix('/') {
+                if open.pop() != Some(name) {
+                    return false;
+                }
+            } else if !token.ends_with('/') {
+                open.push(token.split(' ').next().unwrap_or_default());
+            }
+        }
+        open.is_empty() && !rest.contains('>')
+    }
+
+    #[test]
+    fn test_to_mathml() {
+        let cases = [
+            ("x", "<mi>x</mi>"),
+            ("-3", "<mrow><mo>-</mo><mn>3</mn></mrow>"),
+            ("x^-1", "<mfrac><mn>1</mn><mi>x</mi></mfrac>"),
+            (
+                "sin(x^2)",
+                "<mrow><mi>sin</mi><mo>&#x2061;</mo><mrow><mo>(</mo><msup><mi>x</mi><mn>2</mn></msup><mo>)</mo></mrow></mrow>",
+            ),
+            (
+                "exp(2x) - 1",
+                "<mrow><msup><mi>e</mi><mrow><mn>2</mn><mo>&#x2062;</mo><mi>x</mi></mrow></msup><mo>-</mo><mn>1</mn></mrow>",
+            ),
+            (
+                "-x / (x + 1)^2",
+                "<mrow><mo>-</mo><mfrac><mi>x</mi><msup><mrow><mo>(</mo><mrow><mi>x</mi><mo>+</mo><mn>1</mn></mrow><mo>)</mo></mrow><mn>2</mn></msup></mfrac></mrow>",
+            ),
+            (
+                "x - (x + 1)",
+                "<mrow><mi>x</mi><mo>-</mo><mrow><mo>(</mo><mrow><mi>x</mi><mo>+</mo><mn>1</mn></mrow><mo>)</mo></mrow></mrow>",
+            ),
+            (
+                "-(x + 1)",
+                "<mrow><mo>-</mo><mrow><mo>(</mo><mrow><mi>x</mi><mo>+</mo><mn>1</mn></mrow><mo>)</mo></mrow></mrow>",
+            ),
+            (
+                "x * log(x)",
+                "<mrow><mi>x</mi><mo>&#x22c5;</mo><mrow><mi>ln</mi><mo>&#x2061;</mo><mrow><mo>(</mo><mi>x</mi><mo>)</mo></mrow></mrow></mrow>",
+            ),
+        ];
+
+        for case in cases {
+            assert_eq!(
+                parse(case.0).unwrap().to_mathml(),
+                format!(
+                    "<math xmlns=\"http://www.w3.org/1998/Math/MathML\">{}</math>",
+                    case.1
+                ),
+                "{}",
+                case.0
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_mathml_derivative() {
+        // Only a `Fold` can build this, as `Derivative::new` rejects it.
+        let derivative = Derivative {
+            func: Box::new(sin(x())),
+            order: 2,
+            wrt: 0,
+        };
+        let mathml = pow(derivative, 3).to_mathml();
+        assert_eq!(
+            mathml,
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><msup><mrow><msubsup><mo>&#x2202;</mo><mn>0</mn><mn>2</mn></msubsup><mrow><mo>(</mo><mrow><mi>sin</mi><mo>&#x2061;</mo><mrow><mo>(</mo><mi>x</mi><mo>)</mo></mrow></mrow><mo>)</mo></mrow></mrow><mn>3</mn></msup></math>"
+        );
+        assert!(well_formed(&mathml), "{}", mathml);
+    }
+
+    #[test]
+    fn test_to_mathml_well_formed() {
+        let corpus = [
+            "0",
+            "-12",
+            "x",
+            "exp(x)",
+            "exp(-x^2)",
+            "log(x)",
+            "sin(x)",
+            "cos(x)",
+            "tan(x)",
+            "x^0",
+            "x^-3",
+            "(-2)^3",
+            "(x^2)^3",
+            "exp(x)^2",
+            "x - 2x + 3",
+            "x + (-x + 1)",
+            "x * -2",
+            "-x * cos(x)",
+            "2 * 3 * x",
+            "(x + 1) * (x - 1)",
+            "1 / -2",
+            "x / x^2 / tan(x)",
+            "(1 - x) / x",
+            "sin(x) / (x + 1)",
+            "tan(cos(x)) * exp(x)",
+        ];
+
+        for source in corpus {
+            let expression = parse(source).unwrap();
+            for mathml in [expression.to_mathml(), expression.diff().to_mathml()] {
+                assert!(well_formed(&mathml), "{}: {}", source, mathml);
+            }
+        }
+        let escaped = parse("x").unwrap().to_mathml_with_variable("a<b&c");
+        assert!(well_formed(&escaped), "{}", escaped);
+        assert!(!well_formed("<mrow><mi>x</mrow></mi>"));
+        assert!(!well_formed("<mi>a&b</mi>"));
+    }
+}
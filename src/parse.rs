@@ -0,0 +1,409 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::function::lookup;
+use crate::{Base, Cos, Custom, Exp, Expression, Log, Pow, Scaler, Sin, Tan, X};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    /// Byte offset into the input.
+    pub position: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl FromStr for Expression {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s)
+    }
+}
+
+/// Parses infix notation such as `sin(x^2) + 3x / (x - 1)`.
+pub fn parse(input: &str) -> Result<Expression, ParseError> {
+    parse_with_variable(input, "x")
+}
+
+/// Like `parse`, but the variable is spelled `variable` instead of `x`.
+pub fn parse_with_variable(input: &str, variable: &str) -> Result<Expression, ParseError> {
+    Parser {
+        tokens: tokenize(input)?,
+        index: 0,
+        variable,
+    }
+    .parse()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(Scaler),
+    Ident(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Caret,
+    LParen,
+    RParen,
+    End,
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    variable: &'a str,
+}
+
+impl Parser<'_> {
+    fn parse(mut self) -> Result<Expression, ParseError> {
+        let expression = self.expression()?;
+        match self.peek() {
+            Token::End => Ok(expression),
+            Token::RParen => Err(self.error("unmatched `)`")),
+            _ => Err(self.error("expected an operator")),
+        }
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index].0
+    }
+
+    fn position(&self) -> usize {
+        self.tokens[self.index].1
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.index].0.clone();
+        if token != Token::End {
+            self.index += 1;
+        }
+        token
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            message: message.to_string(),
+            position: self.position(),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expression, ParseError> {
+        let mut terms = vec![self.term()?];
+        loop {
+            match self.peek() {
+                Token::Plus => {
+                    self.next();
+                    terms.push(self.term()?);
+                }
+                Token::Minus => {
+                    self.next();
+                    let term = self.term()?;
+                    terms.push(negate(term));
+                }
+                _ => break,
+            }
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expression::Add(terms)
+        })
+    }
+
+    fn term(&mut self) -> Result<Expression, ParseError> {
+        let mut factors = vec![self.unary()?];
+        loop {
+            match self.peek() {
+                Token::Star => {
+                    self.next();
+                    factors.push(self.unary()?);
+                }
+                Token::Slash => {
+                    self.next();
+                    let factor = self.unary()?;
+                    factors.push(Expression::Base(Base::Pow(Pow {
+                        exp: -1,
+                        arg: Box::new(factor),
+                    })));
+                }
+                // Implicit multiplication, as in `2x` or `x sin(x)`.
+                Token::Number(_) | Token::Ident(_) | Token::LParen => factors.push(self.power()?),
+                _ => break,
+            }
+        }
+        Ok(if factors.len() == 1 {
+            factors.remove(0)
+        } else {
+            Expression::Mul(factors)
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expression, ParseError> {
+        match self.peek() {
+            Token::Minus => {
+                self.next();
+                Ok(negate(self.unary()?))
+            }
+            Token::Plus => {
+                self.next();
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<Expression, ParseError> {
+        let base = self.primary()?;
+        if *self.peek() != Token::Caret {
+            return Ok(base);
+        }
+        self.next();
+        let exp = self.exponent()?;
+        Ok(Expression::Base(Base::Pow(Pow {
+            exp,
+            arg: Box::new(base),
+        })))
+    }
+
+    fn exponent(&mut self) -> Result<isize, ParseError> {
+        let parenthesized = *self.peek() == Token::LParen;
+        if parenthesized {
+            self.next();
+        }
+        let negative = match self.peek() {
+            Token::Minus => {
+                self.next();
+                true
+            }
+            Token::Plus => {
+                self.next();
+                false
+            }
+            _ => false,
+        };
+        let exp = match self.peek() {
+            Token::Number(n) if negative => -*n,
+            Token::Number(n) => *n,
+            _ => return Err(self.error("exponent must be an integer")),
+        };
+        self.next();
+        if parenthesized {
+            if *self.peek() != Token::RParen {
+                return Err(self.error("exponent must be an integer"));
+            }
+            self.next();
+        }
+        Ok(exp)
+    }
+
+    fn primary(&mut self) -> Result<Expression, ParseError> {
+        let position = self.position();
+        match self.next() {
+            Token::Number(n) => Ok(Expression::Base(Base::Scaler(n))),
+            Token::Ident(name) if name == self.variable => Ok(Expression::Base(Base::X(X {}))),
+            Token::Ident(name) => {
+                if *self.peek() != Token::LParen {
+                    return Err(ParseError {
+                        message: format!("unknown variable `{}`", name),
+                        position,
+                    });
+                }
+                let arg = Box::new(self.parenthesized()?);
+                Ok(Expression::Base(match name.as_str() {
+                    "exp" => Base::Exp(Exp { arg }),
+                    "log" | "ln" => Base::Log(Log { arg }),
+                    "sin" => Base::Sin(Sin { arg }),
+                    "cos" => Base::Cos(Cos { arg }),
+                    "tan" => Base::Tan(Tan { arg }),
+                    name if lookup(name).is_some() => Base::Custom(Custom {
+                        name: name.to_string(),
+                        arg,
+                    }),
+                    _ => {
+                        return Err(ParseError {
+                            message: format!("unknown function `{}`", name),
+                            position,
+                        })
+                    }
+                }))
+            }
+            Token::LParen => {
+                self.index -= 1;
+                self.parenthesized()
+            }
+            Token::End => Err(ParseError {
+                message: "unexpected end of input".to_string(),
+                position,
+            }),
+            _ => Err(ParseError {
+                message: "expected a number, variable or function".to_string(),
+                position,
+            }),
+        }
+    }
+
+    fn parenthesized(&mut self) -> Result<Expression, ParseError> {
+        let open = self.position();
+        self.next();
+        let expression = self.expression()?;
+        match self.peek() {
+            Token::RParen => {
+                self.next();
+                Ok(expression)
+            }
+            Token::End => Err(ParseError {
+                message: "unclosed `(`".to_string(),
+                position: open,
+            }),
+            _ => Err(self.error("expected `)`")),
+        }
+    }
+}
+
+pub(crate) fn negate(expression: Expression) -> Expression {
+    match expression {
+        Expression::Base(Base::Scaler(n)) if n != Scaler::MIN => Expression::Base(Base::Scaler(-n)),
+        expression => Expression::Mul(vec![Expression::Base(Base::Scaler(-1)), expression]),
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+    while let Some(&(position, c)) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '0'..='9' => {
+                let mut end = position;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !c.is_ascii_digit() {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                if let Some(&(_, '.')) = chars.peek() {
+                    return Err(ParseError {
+                        message: "only integer constants are supported".to_string(),
+                        position,
+                    });
+                }
+                match input[position..end].parse() {
+                    Ok(n) => Token::Number(n),
+                    Err(_) => {
+                        return Err(ParseError {
+                            message: "integer constant is too large".to_string(),
+                            position,
+                        })
+                    }
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = position;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                Token::Ident(input[position..end].to_string())
+            }
+            _ => {
+                chars.next();
+                match c {
+                    '+' => Token::Plus,
+                    '-' => Token::Minus,
+                    '*' => Token::Star,
+                    '/' => Token::Slash,
+                    '^' => Token::Caret,
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    _ => {
+                        return Err(ParseError {
+                            message: format!("unexpected character `{}`", c),
+                            position,
+                        })
+                    }
+                }
+            }
+        };
+        tokens.push((token, position));
+    }
+    tokens.push((Token::End, input.len()));
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{exp, log, pow, sin, x};
+    use crate::parse::{parse, parse_with_variable, ParseError};
+    use crate::Expression;
+
+    #[test]
+    fn test_parse() {
+        let cases = [
+            ("3", Expression::from(3)),
+            ("-3", Expression::from(-3)),
+            ("x", x()),
+            ("sin(x^2)", sin(pow(x(), 2))),
+            ("x - exp(x) + 1", x() - exp(x()) + 1),
+            ("2x / ln(x)", 2 * x() / log(x())),
+            ("(x + 1)^(-2)", pow(x() + 1, -2)),
+            ("-x^2", -pow(x(), 2)),
+        ];
+
+        for case in cases {
+            assert_eq!(parse(case.0), Ok(case.1), "{}", case.0);
+        }
+    }
+
+    #[test]
+    fn test_parse_with_variable() {
+        assert_eq!(parse_with_variable("sin(t)", "t"), Ok(sin(x())));
+        assert_eq!(
+            parse_with_variable("x", "t"),
+            Err(ParseError {
+                message: "unknown variable `x`".to_string(),
+                position: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let cases = [
+            ("", "unexpected end of input", 0),
+            ("sin(x", "unclosed `(`", 3),
+            ("x)", "unmatched `)`", 1),
+            ("x^y", "exponent must be an integer", 2),
+            ("1.5", "only integer constants are supported", 0),
+            ("foo(x)", "unknown function `foo`", 0),
+            ("2 * y", "unknown variable `y`", 4),
+            ("x # 1", "unexpected character `#`", 2),
+        ];
+
+        for case in cases {
+            assert_eq!(
+                parse(case.0),
+                Err(ParseError {
+                    message: case.1.to_string(),
+                    position: case.2,
+                }),
+                "{}",
+                case.0
+            );
+        }
+    }
+}
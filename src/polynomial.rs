@@ -0,0 +1,577 @@
+//! Polynomials in `x` with exact rational coefficients.
+//!
+//! `Expression::to_polynomial` recognises expressions built from constants,
+//! `x`, sums, products and integer powers, where negative powers are only
+//! allowed on constants. The arithmetic panics when a coefficient overflows,
+//! like the integer operators do; the conversions return `None` instead.
+
+use std::fmt;
+use std::ops;
+
+use crate::{Base, Expression, Pow, Scaler, X};
+
+/// The highest degree `to_polynomial` will build, so that `x^1000000000`
+/// does not allocate a billion coefficients.
+pub const MAX_DEGREE: usize = 1 << 12;
+
+/// A fraction in lowest terms with a positive denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numer: i128,
+    denom: i128,
+}
+
+impl Rational {
+    pub const ZERO: Rational = Rational { numer: 0, denom: 1 };
+    pub const ONE: Rational = Rational { numer: 1, denom: 1 };
+
+    /// Panics if `denom` is zero.
+    pub fn new(numer: i128, denom: i128) -> Self {
+        assert!(denom != 0, "zero denominator");
+        Self::checked_new(numer, denom).expect("rational overflow")
+    }
+
+    fn checked_new(numer: i128, denom: i128) -> Option<Self> {
+        if denom == 0 {
+            return None;
+        }
+        let divisor = gcd(numer.unsigned_abs(), denom.unsigned_abs());
+        let (mut numer, mut denom) = (numer / divisor as i128, denom / divisor as i128);
+        if denom < 0 {
+            numer = numer.checked_neg()?;
+            denom = denom.checked_neg()?;
+        }
+        Some(Self { numer, denom })
+    }
+
+    pub fn numer(&self) -> i128 {
+        self.numer
+    }
+
+    pub fn denom(&self) -> i128 {
+        self.denom
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numer == 0
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let numer = self
+            .numer
+            .checked_mul(other.denom)?
+            .checked_add(other.numer.checked_mul(self.denom)?)?;
+        Self::checked_new(numer, self.denom.checked_mul(other.denom)?)
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        Some(Self {
+            numer: self.numer.checked_neg()?,
+            denom: self.denom,
+        })
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        Self::checked_new(
+            self.numer.checked_mul(other.numer)?,
+            self.denom.checked_mul(other.denom)?,
+        )
+    }
+
+    /// `None` on overflow or when `other` is zero.
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        Self::checked_new(
+            self.numer.checked_mul(other.denom)?,
+            self.denom.checked_mul(other.numer)?,
+        )
+    }
+
+    fn checked_pow(self, exp: isize) -> Option<Self> {
+        let base = if exp < 0 {
+            Self::ONE.checked_div(self)?
+        } else {
+            self
+        };
+        let exp = u32::try_from(exp.unsigned_abs()).ok()?;
+        Some(Self {
+            numer: base.numer.checked_pow(exp)?,
+            denom: base.denom.checked_pow(exp)?,
+        })
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.max(1)
+}
+
+impl From<i128> for Rational {
+    fn from(value: i128) -> Self {
+        Self {
+            numer: value,
+            denom: 1,
+        }
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.denom == 1 {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+impl ops::Add for Rational {
+    type Output = Rational;
+
+    fn add(self, rhs: Rational) -> Rational {
+        self.checked_add(rhs).expect("rational overflow")
+    }
+}
+
+impl ops::Sub for Rational {
+    type Output = Rational;
+
+    fn sub(self, rhs: Rational) -> Rational {
+        ops::Add::add(self, -rhs)
+    }
+}
+
+impl ops::Mul for Rational {
+    type Output = Rational;
+
+    fn mul(self, rhs: Rational) -> Rational {
+        self.checked_mul(rhs).expect("rational overflow")
+    }
+}
+
+impl ops::Div for Rational {
+    type Output = Rational;
+
+    fn div(self, rhs: Rational) -> Rational {
+        assert!(!rhs.is_zero(), "division by zero");
+        self.checked_div(rhs).expect("rational overflow")
+    }
+}
+
+impl ops::Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        self.checked_neg().expect("rational overflow")
+    }
+}
+
+/// A dense polynomial: `coefficients()[i]` belongs to `x^i`, and the leading
+/// coefficient is never zero.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Polynomial {
+    coefficients: Vec<Rational>,
+}
+
+impl Polynomial {
+    pub fn new(mut coefficients: Vec<Rational>) -> Self {
+        while coefficients.last().is_some_and(Rational::is_zero) {
+            coefficients.pop();
+        }
+        Self { coefficients }
+    }
+
+    pub fn zero() -> Self {
+        Self::new(vec![])
+    }
+
+    pub fn constant(value: Rational) -> Self {
+        Self::new(vec![value])
+    }
+
+    pub fn x() -> Self {
+        Self::new(vec![Rational::ZERO, Rational::ONE])
+    }
+
+    pub fn coefficients(&self) -> &[Rational] {
+        &self.coefficients
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    /// `None` for the zero polynomial.
+    pub fn degree(&self) -> Option<usize> {
+        self.coefficients.len().checked_sub(1)
+    }
+
+    pub fn leading(&self) -> Rational {
+        self.coefficients.last().copied().unwrap_or(Rational::ZERO)
+    }
+
+    pub fn eval(&self, x: f64) -> f64 {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(0.0, |value, c| value * x + c.numer as f64 / c.denom as f64)
+    }
+
+    pub fn derivative(&self) -> Polynomial {
+        self.checked_derivative().expect("rational overflow")
+    }
+
+    /// Returns the quotient and remainder, with the remainder of lower degree
+    /// than `divisor`. Panics if `divisor` is zero.
+    pub fn div_rem(&self, divisor: &Polynomial) -> (Polynomial, Polynomial) {
+        assert!(!divisor.is_zero(), "division by the zero polynomial");
+        self.checked_div_rem(divisor).expect("rational overflow")
+    }
+
+    /// The monic greatest common divisor, or zero if both are zero.
+    pub fn gcd(&self, other: &Polynomial) -> Polynomial {
+        let (mut a, mut b) = (self.clone(), other.clone());
+        while !b.is_zero() {
+            let (_, remainder) = a.div_rem(&b);
+            (a, b) = (b, remainder);
+        }
+        if a.is_zero() {
+            return a;
+        }
+        let leading = a.leading();
+        Polynomial::new(a.coefficients.into_iter().map(|c| c / leading).collect())
+    }
+
+    fn checked_add(&self, other: &Polynomial) -> Option<Polynomial> {
+        let len = self.coefficients.len().max(other.coefficients.len());
+        let coefficient = |p: &Polynomial, i| p.coefficients.get(i).copied();
+        (0..len)
+            .map(|i| {
+                let a = coefficient(self, i).unwrap_or(Rational::ZERO);
+                a.checked_add(coefficient(other, i).unwrap_or(Rational::ZERO))
+            })
+            .collect::<Option<_>>()
+            .map(Polynomial::new)
+    }
+
+    fn checked_neg(&self) -> Option<Polynomial> {
+        self.coefficients
+            .iter()
+            .map(|c| c.checked_neg())
+            .collect::<Option<_>>()
+            .map(Polynomial::new)
+    }
+
+    fn checked_mul(&self, other: &Polynomial) -> Option<Polynomial> {
+        if self.is_zero() || other.is_zero() {
+            return Some(Polynomial::zero());
+        }
+        let len = self.coefficients.len() + other.coefficients.len() - 1;
+        let mut coefficients = vec![Rational::ZERO; len];
+        for (i, a) in self.coefficients.iter().enumerate() {
+            for (j, b) in other.coefficients.iter().enumerate() {
+                coefficients[i + j] = coefficients[i + j].checked_add(a.checked_mul(*b)?)?;
+            }
+        }
+        Some(Polynomial::new(coefficients))
+    }
+
+    fn checked_pow(&self, exp: usize) -> Option<Polynomial> {
+        let degree = self.degree().unwrap_or(0).checked_mul(exp)?;
+        if degree > MAX_DEGREE {
+            return None;
+        }
+        let mut result = Polynomial::constant(Rational::ONE);
+        for _ in 0..exp {
+            result = result.checked_mul(self)?;
+        }
+        Some(result)
+    }
+
+    fn checked_derivative(&self) -> Option<Polynomial> {
+        self.coefficients
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, c)| c.checked_mul(Rational::from(i as i128)))
+            .collect::<Option<_>>()
+            .map(Polynomial::new)
+    }
+
+    fn checked_div_rem(&self, divisor: &Polynomial) -> Option<(Polynomial, Polynomial)> {
+        let divisor_degree = divisor.degree()?;
+        let mut quotient = vec![Rational::ZERO; self.coefficients.len()];
+        let mut remainder = self.clone();
+        while let Some(degree) = remainder.degree().filter(|d| *d >= divisor_degree) {
+            let factor = remainder.leading().checked_div(divisor.leading())?;
+            let shift = degree - divisor_degree;
+            quotient[shift] = factor;
+            let mut coefficients = remainder.coefficients;
+            for (i, c) in divisor.coefficients.iter().enumerate() {
+                let term = c.checked_mul(factor)?.checked_neg()?;
+                coefficients[i + shift] = coefficients[i + shift].checked_add(term)?;
+            }
+            remainder = Polynomial::new(coefficients);
+        }
+        Some((Polynomial::new(quotient), remainder))
+    }
+
+    /// Converts back into a sum of terms, highest degree first, or `None` if a
+    /// coefficient does not fit in a `Scaler`.
+    pub fn to_expression(&self) -> Option<Expression> {
+        let mut terms = vec![];
+        for (degree, c) in self.coefficients.iter().enumerate().rev() {
+            if c.is_zero() {
+                continue;
+            }
+            let numer = Scaler::try_from(c.numer).ok()?;
+            let denom = Scaler::try_from(c.denom).ok()?;
+            let mut factors = vec![];
+            if numer != 1 || degree == 0 {
+                factors.push(Expression::Base(Base::Scaler(numer)));
+            }
+            match degree {
+                0 => {}
+                1 => factors.push(Expression::Base(Base::X(X {}))),
+                _ => factors.push(Expression::Base(Base::Pow(Pow::new(
+                    degree as isize,
+                    Expression::Base(Base::X(X {})),
+                )))),
+            }
+            if denom != 1 {
+                factors.push(Expression::Base(Base::Pow(Pow::new(
+                    -1,
+                    Expression::Base(Base::Scaler(denom)),
+                ))));
+            }
+            terms.push(match factors.len() {
+                1 => factors.remove(0),
+                _ => Expression::Mul(factors),
+            });
+        }
+        Some(match terms.len() {
+            0 => Expression::Base(Base::Scaler(0)),
+            1 => terms.remove(0),
+            _ => Expression::Add(terms),
+        })
+    }
+}
+
+impl ops::Add for &Polynomial {
+    type Output = Polynomial;
+
+    fn add(self, rhs: &Polynomial) -> Polynomial {
+        self.checked_add(rhs).expect("rational overflow")
+    }
+}
+
+impl ops::Sub for &Polynomial {
+    type Output = Polynomial;
+
+    fn sub(self, rhs: &Polynomial) -> Polynomial {
+        ops::Add::add(self, &-rhs)
+    }
+}
+
+impl ops::Mul for &Polynomial {
+    type Output = Polynomial;
+
+    fn mul(self, rhs: &Polynomial) -> Polynomial {
+        self.checked_mul(rhs).expect("rational overflow")
+    }
+}
+
+impl ops::Neg for &Polynomial {
+    type Output = Polynomial;
+
+    fn neg(self) -> Polynomial {
+        self.checked_neg().expect("rational overflow")
+    }
+}
+
+impl Expression {
+    /// Collects the expression into a polynomial in `x`, or `None` if it is
+    /// not one, its degree exceeds `MAX_DEGREE` or a coefficient overflows.
+    pub fn to_polynomial(&self) -> Option<Polynomial> {
+        match self {
+            Self::Base(Base::Scaler(scaler)) => {
+                Some(Polynomial::constant(Rational::from(*scaler as i128)))
+            }
+            Self::Base(Base::X(_)) => Some(Polynomial::x()),
+            Self::Base(Base::Pow(pow)) => {
+                let base = pow.arg.to_polynomial()?;
+                match base.degree() {
+                    None | Some(0) => {
+                        Some(Polynomial::constant(base.leading().checked_pow(pow.exp)?))
+                    }
+                    _ => base.checked_pow(usize::try_from(pow.exp).ok()?),
+                }
+            }
+            Self::Base(_) => None,
+            Self::Add(add) => add.iter().try_fold(Polynomial::zero(), |sum, term| {
+                sum.checked_add(&term.to_polynomial()?)
+            }),
+            Self::Mul(mul) => mul
+                .iter()
+                .try_fold(Polynomial::constant(Rational::ONE), |product, factor| {
+                    product.checked_mul(&factor.to_polynomial()?)
+                }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{exp, pow, sin, x};
+    use crate::polynomial::{Polynomial, Rational};
+    use crate::Expression;
+
+    fn polynomial(coefficients: &[i128]) -> Polynomial {
+        Polynomial::new(coefficients.iter().map(|c| Rational::from(*c)).collect())
+    }
+
+    #[test]
+    fn test_rational() {
+        let cases = [
+            (Rational::new(2, 4), "1/2"),
+            (Rational::new(3, -6), "-1/2"),
+            (Rational::new(0, -5), "0"),
+            (Rational::new(1, 3) + Rational::new(1, 6), "1/2"),
+            (
+                Rational::new(2, 3) * Rational::new(3, 4) - Rational::ONE,
+                "-1/2",
+            ),
+            (Rational::new(5, 7) / Rational::new(-10, 21), "-3/2"),
+        ];
+
+        for case in cases {
+            assert_eq!(case.0.to_string(), case.1);
+        }
+    }
+
+    #[test]
+    fn test_to_polynomial() {
+        let cases = [
+            (x(), Some(polynomial(&[0, 1]))),
+            (Expression::from(-4), Some(polynomial(&[-4]))),
+            (pow(x() + 1, 2), Some(polynomial(&[1, 2, 1]))),
+            ((x() - 1) * (x() + 1) - pow(x(), 2), Some(polynomial(&[-1]))),
+            (
+                pow(x(), 3) / 2 + pow(2, -2),
+                Some(Polynomial::new(vec![
+                    Rational::new(1, 4),
+                    Rational::ZERO,
+                    Rational::ZERO,
+                    Rational::new(1, 2),
+                ])),
+            ),
+            (pow(x(), -1), None),
+            (sin(x()) + 1, None),
+            (x() * exp(x()), None),
+            (pow(x(), 1_000_000_000), None),
+            (pow(0, -1), None),
+        ];
+
+        for case in cases {
+            assert_eq!(case.0.to_polynomial(), case.1, "{}", case.0);
+        }
+    }
+
+    #[test]
+    fn test_to_expression() {
+        let cases = [
+            (polynomial(&[]), "0"),
+            (polynomial(&[-3]), "-3"),
+            (polynomial(&[0, 1]), "x"),
+            (polynomial(&[1, -1, 0, 2]), "2 * x^3 - x + 1"),
+            (polynomial(&[0, 0, -1]), "-x^2"),
+            (
+                Polynomial::new(vec![Rational::new(-1, 3), Rational::new(3, 2)]),
+                "3 * x / 2 - 3^-1",
+            ),
+        ];
+
+        for case in cases {
+            let expression = case.0.to_expression().unwrap();
+            assert_eq!(expression.to_string(), case.1);
+            assert_eq!(expression.to_polynomial(), Some(case.0));
+        }
+        let large = Polynomial::constant(Rational::from(i128::MAX));
+        assert_eq!(large.to_expression(), None);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let cases = [
+            (polynomial(&[1, 2, 1]), polynomial(&[1, 1])),
+            (polynomial(&[-1, 0, 0, 1]), polynomial(&[-1, 2])),
+            (polynomial(&[5, 0, 3, 0, 1]), polynomial(&[1, 0, 1])),
+            (polynomial(&[2]), polynomial(&[0, 0, 3])),
+            (polynomial(&[]), polynomial(&[7])),
+        ];
+
+        for (a, b) in cases {
+            let (quotient, remainder) = a.div_rem(&b);
+            assert_eq!(&(&quotient * &b) + &remainder, a);
+            assert!(remainder.degree() < b.degree(), "{:?}", remainder);
+            assert_eq!(&(&a + &b) - &b, a);
+            assert_eq!(
+                (&a * &b).eval(1.5),
+                a.eval(1.5) * b.eval(1.5),
+                "{:?} * {:?}",
+                a,
+                b
+            );
+        }
+    }
+
+    #[test]
+    fn test_gcd() {
+        let cases = [
+            (
+                polynomial(&[-1, 0, 1]),
+                polynomial(&[1, 2, 1]),
+                polynomial(&[1, 1]),
+            ),
+            (
+                polynomial(&[0, 2]),
+                polynomial(&[0, 0, 6]),
+                polynomial(&[0, 1]),
+            ),
+            (polynomial(&[1, 1]), polynomial(&[-1, 1]), polynomial(&[1])),
+            (
+                polynomial(&[0, 0, 4]),
+                polynomial(&[]),
+                polynomial(&[0, 0, 1]),
+            ),
+            (polynomial(&[]), polynomial(&[]), polynomial(&[])),
+        ];
+
+        for case in cases {
+            assert_eq!(case.0.gcd(&case.1), case.2);
+            assert_eq!(case.1.gcd(&case.0), case.2);
+        }
+    }
+
+    #[test]
+    fn test_derivative() {
+        let cases = [
+            (pow(x() + 1, 3), "3 * x^2 + 6 * x + 3"),
+            (x() * (x() - 2) / 4, "x / 2 - 2^-1"),
+            (Expression::from(7), "0"),
+        ];
+
+        for case in cases {
+            let polynomial = case.0.to_polynomial().unwrap();
+            let derivative = polynomial.derivative().to_expression().unwrap();
+            assert_eq!(derivative.to_string(), case.1);
+            assert_eq!(
+                derivative.to_polynomial(),
+                case.0.diff().to_polynomial(),
+                "{}",
+                case.0
+            );
+        }
+    }
+}
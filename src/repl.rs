@@ -0,0 +1,206 @@
+use std::io::{self, BufRead, Write};
+
+use diff_rs::parse::parse;
+use diff_rs::Expression;
+
+const HELP: &str = "\
+Type an expression in x to print its derivative, e.g. `sin(x^2) / x`.
+Commands act on the last expression:
+  :simplify    print the simplified derivative
+  :eval <x>    evaluate the expression and its derivative at x
+  :diff <n>    print the n-th derivative
+  :tree        print the expression tree
+  :trace       show the rules used to find the derivative
+  :history     list previous inputs; `!<n>` runs input n again
+  :help        show this message
+  :quit        exit";
+
+struct Repl {
+    current: Option<Expression>,
+    history: Vec<String>,
+}
+
+/// Reads lines from `input` until end of input or `:quit`. Errors are
+/// printed and the loop continues.
+pub fn run(input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut repl = Repl {
+        current: None,
+        history: vec![],
+    };
+    let mut lines = input.lines();
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        let mut line = line.trim().to_string();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(index) = line.strip_prefix('!') {
+            match index
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| repl.history.get(i.checked_sub(1)?))
+            {
+                Some(recalled) => {
+                    line = recalled.clone();
+                    writeln!(output, "{}", line)?;
+                }
+                None => {
+                    writeln!(output, "error: no history entry `{}`", index)?;
+                    continue;
+                }
+            }
+        }
+        repl.history.push(line.clone());
+        match repl.eval(&line) {
+            Ok(Some(response)) => writeln!(output, "{}", response)?,
+            Ok(None) => break,
+            Err(error) => writeln!(output, "error: {}", error)?,
+        }
+    }
+    Ok(())
+}
+
+impl Repl {
+    // Returns `Ok(None)` when the session should end.
+    fn eval(&mut self, line: &str) -> Result<Option<String>, String> {
+        let command = match line.strip_prefix(':') {
+            Some(command) => command,
+            None => {
+                let expression = parse(line).map_err(|e| e.to_string())?;
+                let derivative = diff(&expression)?.to_string();
+                self.current = Some(expression);
+                return Ok(Some(derivative));
+            }
+        };
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+        let response = match name {
+            "quit" | "q" => return Ok(None),
+            "help" => HELP.to_string(),
+            "history" => self
+                .history
+                .iter()
+                .enumerate()
+                .map(|(i, line)| format!("{:>4}  {}", i + 1, line))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            "simplify" => diff(self.current()?)?.simplify().to_string(),
+            "tree" => format!("{:#?}", self.current()?),
+            "trace" => {
+                let expression = self.current()?;
+                diff(expression)?;
+                expression.diff_with_trace().to_string()
+            }
+            "eval" => {
+                let x: f64 = argument
+                    .parse()
+                    .map_err(|_| format!("expected a number, found `{}`", argument))?;
+                let expression = self.current()?;
+                let value = expression.compile().call(x).map_err(|e| e.to_string())?;
+                let slope = diff(expression)?
+                    .compile()
+                    .call(x)
+                    .map_err(|e| e.to_string())?;
+                format!("f({}) = {}\nf'({}) = {}", x, value, x, slope)
+            }
+            "diff" => {
+                let order: usize = match argument {
+                    "" => 1,
+                    argument => argument
+                        .parse()
+                        .map_err(|_| format!("expected an order, found `{}`", argument))?,
+                };
+                let mut derivative = self.current()?.clone();
+                for _ in 0..order {
+                    derivative = diff(&derivative)?.simplify();
+                }
+                derivative.to_string()
+            }
+            name => return Err(format!("unknown command `:{}`, try `:help`", name)),
+        };
+        Ok(Some(response))
+    }
+
+    fn current(&self) -> Result<&Expression, String> {
+        self.current
+            .as_ref()
+            .ok_or_else(|| "no expression yet, type one first".to_string())
+    }
+}
+
+fn diff(expression: &Expression) -> Result<Expression, String> {
+    expression.try_diff().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repl::run;
+
+    #[test]
+    fn test_run() {
+        let input = "\
+:simplify
+x^3 + sin(x)
+:simplify
+:diff 3
+:eval 0
+log(x
+:eval 2.5x
+:frobnicate
+log(x)
+:eval -1
+:trace
+x^-9223372036854775807
+:diff 2
+!2
+:history
+:quit
+x
+";
+        let mut output = vec![];
+        run(input.as_bytes(), &mut output).unwrap();
+
+        let expected = "\
+> error: no expression yet, type one first
+> 3 * x^2 * 1 + cos(x) * 1
+> 3 * x^2 + cos(x)
+> -cos(x) + 6
+> f(0) = 0
+f'(0) = 1
+> error: unclosed `(` at column 4
+> error: expected a number, found `2.5x`
+> error: unknown command `:frobnicate`, try `:help`
+> 1 / x
+> error: `log` is undefined at -1
+> chain rule (log): d/dx log(x) = 1 / x
+  variable rule: d/dx x = 1
+> -9223372036854775807 * x^-9223372036854775808 * 1
+> error: exponent -9223372036854775808 - 1 overflows
+> x^3 + sin(x)
+3 * x^2 * 1 + cos(x) * 1
+>    1  :simplify
+   2  x^3 + sin(x)
+   3  :simplify
+   4  :diff 3
+   5  :eval 0
+   6  log(x
+   7  :eval 2.5x
+   8  :frobnicate
+   9  log(x)
+  10  :eval -1
+  11  :trace
+  12  x^-9223372036854775807
+  13  :diff 2
+  14  x^3 + sin(x)
+  15  :history
+> ";
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+}
@@ -0,0 +1,589 @@
+//! The JSON interchange format for expressions.
+//!
+//! A document wraps one expression node together with the format version:
+//!
+//! ```json
+//! {"version": 1, "expression": {"op": "sin", "arg": {"op": "var"}}}
+//! ```
+//!
+//! Nodes are objects tagged by `op`:
+//!
+//! | `op`                                  | other members                  |
+//! |---------------------------------------|--------------------------------|
+//! | `"const"`                             | `"value"`: integer             |
+//! | `"var"`                               |                                |
+//! | `"exp"`, `"log"`, `"sin"`, `"cos"`, `"tan"` | `"arg"`: node            |
+//! | `"pow"`                               | `"exp"`: integer, `"arg"`: node |
+//! | `"add"`                               | `"terms"`: array of nodes      |
+//! | `"mul"`                               | `"factors"`: array of nodes    |
+//! | `"call"`                              | `"name"`: string, `"arg"`: node |
+//! | `"apply"`                             | `"name"`: string, `"args"`: array of nodes |
+//! | `"derivative"`                        | `"func"`: node, `"order"`, `"wrt"`: integers |
+//!
+//! Reading is strict: unknown ops, missing, unexpected or repeated members,
+//! integers outside the range of `isize`, calls to functions that are not
+//! registered with `function::register` and other versions are all errors.
+//! The same rules are available as a JSON Schema document in [`JSON_SCHEMA`].
+
+use std::fmt;
+
+use crate::function::lookup;
+use crate::json::{JsonError, Value};
+use crate::{Apply, Base, Cos, Custom, Derivative, Exp, Expression, Log, Pow, Sin, Tan, X};
+
+pub const VERSION: i64 = 1;
+
+pub const JSON_SCHEMA: &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "diff_rs expression",
+  "type": "object",
+  "properties": {
+    "version": {"const": 1},
+    "expression": {"$ref": "#/$defs/node"}
+  },
+  "required": ["version", "expression"],
+  "additionalProperties": false,
+  "$defs": {
+    "node": {
+      "oneOf": [
+        {"$ref": "#/$defs/const"},
+        {"$ref": "#/$defs/var"},
+        {"$ref": "#/$defs/function"},
+        {"$ref": "#/$defs/pow"},
+        {"$ref": "#/$defs/add"},
+        {"$ref": "#/$defs/mul"},
+        {"$ref": "#/$defs/call"},
+        {"$ref": "#/$defs/apply"},
+        {"$ref": "#/$defs/derivative"}
+      ]
+    },
+    "const": {
+      "type": "object",
+      "properties": {"op": {"const": "const"}, "value": {"type": "integer"}},
+      "required": ["op", "value"],
+      "additionalProperties": false
+    },
+    "var": {
+      "type": "object",
+      "properties": {"op": {"const": "var"}},
+      "required": ["op"],
+      "additionalProperties": false
+    },
+    "function": {
+      "type": "object",
+      "properties": {
+        "op": {"enum": ["exp", "log", "sin", "cos", "tan"]},
+        "arg": {"$ref": "#/$defs/node"}
+      },
+      "required": ["op", "arg"],
+      "additionalProperties": false
+    },
+    "pow": {
+      "type": "object",
+      "properties": {
+        "op": {"const": "pow"},
+        "exp": {"type": "integer"},
+        "arg": {"$ref": "#/$defs/node"}
+      },
+      "required": ["op", "exp", "arg"],
+      "additionalProperties": false
+    },
+    "add": {
+      "type": "object",
+      "properties": {
+        "op": {"const": "add"},
+        "terms": {"type": "array", "items": {"$ref": "#/$defs/node"}}
+      },
+      "required": ["op", "terms"],
+      "additionalProperties": false
+    },
+    "mul": {
+      "type": "object",
+      "properties": {
+        "op": {"const": "mul"},
+        "factors": {"type": "array", "items": {"$ref": "#/$defs/node"}}
+      },
+      "required": ["op", "factors"],
+      "additionalProperties": false
+    },
+    "call": {
+      "type": "object",
+      "properties": {
+        "op": {"const": "call"},
+        "name": {"type": "string"},
+        "arg": {"$ref": "#/$defs/node"}
+      },
+      "required": ["op", "name", "arg"],
+      "additionalProperties": false
+    },
+    "apply": {
+      "type": "object",
+      "properties": {
+        "op": {"const": "apply"},
+        "name": {"type": "string"},
+        "args": {"type": "array", "items": {"$ref": "#/$defs/node"}}
+      },
+      "required": ["op", "name", "args"],
+      "additionalProperties": false
+    },
+    "derivative": {
+      "type": "object",
+      "properties": {
+        "op": {"const": "derivative"},
+        "func": {"$ref": "#/$defs/node"},
+        "order": {"type": "integer", "minimum": 0},
+        "wrt": {"type": "integer", "minimum": 0}
+      },
+      "required": ["op", "func", "order", "wrt"],
+      "additionalProperties": false
+    }
+  }
+}"##;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    /// Where the error is, e.g. `$.expression.terms[1].op`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.path)
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl From<JsonError> for SchemaError {
+    fn from(error: JsonError) -> Self {
+        Self {
+            path: "$".to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl Expression {
+    /// Returns a versioned document in the format described in this module.
+    pub fn to_json(&self) -> Value {
+        Value::object([("version", Value::Int(VERSION)), ("expression", node(self))])
+    }
+
+    /// Reads a document written by `to_json`.
+    pub fn from_json(document: &Value) -> Result<Expression, SchemaError> {
+        let path = "$".to_string();
+        let members = members(document, &path, &["version", "expression"])?;
+        let version: i64 = integer(
+            field(members, "version", &path)?,
+            &field_path(&path, "version"),
+        )?;
+        if version != VERSION {
+            return Err(SchemaError {
+                path: field_path(&path, "version"),
+                message: format!("unsupported version {}", version),
+            });
+        }
+        expression(
+            field(members, "expression", &path)?,
+            &field_path(&path, "expression"),
+        )
+    }
+
+    /// Parses JSON text and reads it with `from_json`.
+    pub fn from_json_str(input: &str) -> Result<Expression, SchemaError> {
+        Self::from_json(&crate::json::parse(input)?)
+    }
+}
+
+fn node(expression: &Expression) -> Value {
+    match expression {
+        Expression::Base(base) => match base {
+            Base::Scaler(scaler) => Value::object([
+                ("op", "const".into()),
+                ("value", Value::Int(*scaler as i64)),
+            ]),
+            Base::X(_) => Value::object([("op", "var".into())]),
+            Base::Exp(exp) => function("exp", &exp.arg),
+            Base::Pow(pow) => Value::object([
+                ("op", "pow".into()),
+                ("exp", Value::Int(pow.exp as i64)),
+                ("arg", node(&pow.arg)),
+            ]),
+            Base::Log(log) => function("log", &log.arg),
+            Base::Sin(sin) => function("sin", &sin.arg),
+            Base::Cos(cos) => function("cos", &cos.arg),
+            Base::Tan(tan) => function("tan", &tan.arg),
+            Base::Custom(custom) => Value::object([
+                ("op", "call".into()),
+                ("name", custom.name.as_str().into()),
+                ("arg", node(&custom.arg)),
+            ]),
+            Base::Apply(apply) => Value::object([
+                ("op", "apply".into()),
+                ("name", apply.name.as_str().into()),
+                ("args", Value::Array(apply.args.iter().map(node).collect())),
+            ]),
+            Base::Derivative(derivative) => Value::object([
+                ("op", "derivative".into()),
+                ("func", node(&derivative.func)),
+                ("order", Value::Int(derivative.order as i64)),
+                ("wrt", Value::Int(derivative.wrt as i64)),
+            ]),
+        },
+        Expression::Add(add) => Value::object([
+            ("op", "add".into()),
+            ("terms", Value::Array(add.iter().map(node).collect())),
+        ]),
+        Expression::Mul(mul) => Value::object([
+            ("op", "mul".into()),
+            ("factors", Value::Array(mul.iter().map(node).collect())),
+        ]),
+    }
+}
+
+fn function(op: &str, arg: &Expression) -> Value {
+    Value::object([("op", op.into()), ("arg", node(arg))])
+}
+
+fn expression(value: &Value, path: &str) -> Result<Expression, SchemaError> {
+    let op = match value.get("op") {
+        Some(op) => op.as_str().ok_or_else(|| SchemaError {
+            path: field_path(path, "op"),
+            message: "expected a string".to_string(),
+        })?,
+        None if matches!(value, Value::Object(_)) => return Err(missing(path, "op")),
+        None => {
+            return Err(SchemaError {
+                path: path.to_string(),
+                message: "expected an object".to_string(),
+            })
+        }
+    };
+    let allowed: &[&str] = match op {
+        "const" => &["op", "value"],
+        "var" => &["op"],
+        "exp" | "log" | "sin" | "cos" | "tan" => &["op", "arg"],
+        "pow" => &["op", "exp", "arg"],
+        "add" => &["op", "terms"],
+        "mul" => &["op", "factors"],
+        "call" => &["op", "name", "arg"],
+        "apply" => &["op", "name", "args"],
+        "derivative" => &["op", "func", "order", "wrt"],
+        op => {
+            return Err(SchemaError {
+                path: field_path(path, "op"),
+                message: format!("unknown op `{}`", op),
+            })
+        }
+    };
+    let members = members(value, path, allowed)?;
+    let arg = || -> Result<Box<Expression>, SchemaError> {
+        Ok(Box::new(expression(
+            field(members, "arg", path)?,
+            &field_path(path, "arg"),
+        )?))
+    };
+    let list = |name: &str| -> Result<Vec<Expression>, SchemaError> {
+        let list_path = field_path(path, name);
+        match field(members, name, path)? {
+            Value::Array(values) => values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| expression(value, &format!("{}[{}]", list_path, i)))
+                .collect(),
+            _ => Err(SchemaError {
+                path: list_path,
+                message: "expected an array".to_string(),
+            }),
+        }
+    };
+    Ok(match op {
+        "const" => Expression::Base(Base::Scaler(integer(
+            field(members, "value", path)?,
+            &field_path(path, "value"),
+        )?)),
+        "var" => Expression::Base(Base::X(X {})),
+        "exp" => Expression::Base(Base::Exp(Exp { arg: arg()? })),
+        "log" => Expression::Base(Base::Log(Log { arg: arg()? })),
+        "sin" => Expression::Base(Base::Sin(Sin { arg: arg()? })),
+        "cos" => Expression::Base(Base::Cos(Cos { arg: arg()? })),
+        "tan" => Expression::Base(Base::Tan(Tan { arg: arg()? })),
+        "pow" => Expression::Base(Base::Pow(Pow {
+            exp: integer(field(members, "exp", path)?, &field_path(path, "exp"))?,
+            arg: arg()?,
+        })),
+        "add" => Expression::Add(list("terms")?),
+        "mul" => Expression::Mul(list("factors")?),
+        "apply" => Expression::Base(Base::Apply(Apply {
+            name: string(field(members, "name", path)?, &field_path(path, "name"))?.to_string(),
+            args: list("args")?,
+        })),
+        "derivative" => {
+            let derivative = Derivative::new(
+                expression(field(members, "func", path)?, &field_path(path, "func"))?,
+                integer(field(members, "order", path)?, &field_path(path, "order"))?,
+                integer(field(members, "wrt", path)?, &field_path(path, "wrt"))?,
+            );
+            match derivative {
+                Ok(derivative) => Expression::Base(Base::Derivative(derivative)),
+                Err(message) => {
+                    return Err(SchemaError {
+                        path: path.to_string(),
+                        message,
+                    })
+                }
+            }
+        }
+        _ => {
+            let name_path = field_path(path, "name");
+            let name = string(field(members, "name", path)?, &name_path)?;
+            if lookup(name).is_none() {
+                return Err(SchemaError {
+                    path: name_path,
+                    message: format!("unknown function `{}`", name),
+                });
+            }
+            Expression::Base(Base::Custom(Custom {
+                name: name.to_string(),
+                arg: arg()?,
+            }))
+        }
+    })
+}
+
+// Returns the members of an object, rejecting any not in `allowed` and any
+// that appears twice.
+fn members<'a>(
+    value: &'a Value,
+    path: &str,
+    allowed: &[&str],
+) -> Result<&'a [(String, Value)], SchemaError> {
+    let members = match value {
+        Value::Object(members) => members,
+        _ => {
+            return Err(SchemaError {
+                path: path.to_string(),
+                message: "expected an object".to_string(),
+            })
+        }
+    };
+    if let Some((key, _)) = members
+        .iter()
+        .find(|(key, _)| !allowed.contains(&key.as_str()))
+    {
+        return Err(SchemaError {
+            path: field_path(path, key),
+            message: format!("unexpected member `{}`", key),
+        });
+    }
+    let duplicate = members
+        .iter()
+        .enumerate()
+        .find(|(i, (key, _))| members[..*i].iter().any(|(other, _)| other == key));
+    match duplicate {
+        Some((_, (key, _))) => Err(SchemaError {
+            path: path.to_string(),
+            message: format!("duplicate member `{}`", key),
+        }),
+        None => Ok(members),
+    }
+}
+
+fn field<'a>(
+    members: &'a [(String, Value)],
+    name: &str,
+    path: &str,
+) -> Result<&'a Value, SchemaError> {
+    members
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value)
+        .ok_or_else(|| missing(path, name))
+}
+
+fn missing(path: &str, name: &str) -> SchemaError {
+    SchemaError {
+        path: path.to_string(),
+        message: format!("missing member `{}`", name),
+    }
+}
+
+fn integer<T: TryFrom<i64>>(value: &Value, path: &str) -> Result<T, SchemaError> {
+    let error = |message: &str| SchemaError {
+        path: path.to_string(),
+        message: message.to_string(),
+    };
+    match value {
+        Value::Int(n) => T::try_from(*n).map_err(|_| error("integer out of range")),
+        _ => Err(error("expected an integer")),
+    }
+}
+
+fn string<'a>(value: &'a Value, path: &str) -> Result<&'a str, SchemaError> {
+    value.as_str().ok_or_else(|| SchemaError {
+        path: path.to_string(),
+        message: "expected a string".to_string(),
+    })
+}
+
+fn field_path(path: &str, name: &str) -> String {
+    format!("{}.{}", path, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{apply, pow, sin, x};
+    use crate::json::parse;
+    use crate::schema::{SchemaError, JSON_SCHEMA};
+    use crate::{Derivative, Expression};
+
+    #[test]
+    fn test_json() {
+        let cases = [
+            (
+                "sin(x)",
+                r#"{"version":1,"expression":{"op":"sin","arg":{"op":"var"}}}"#,
+            ),
+            (
+                "(x + 1)^-2",
+                r#"{"version":1,"expression":{"op":"pow","exp":-2,"arg":{"op":"add","terms":[{"op":"var"},{"op":"const","value":1}]}}}"#,
+            ),
+            (
+                "2 exp(x) log(x)",
+                r#"{"version":1,"expression":{"op":"mul","factors":[{"op":"const","value":2},{"op":"exp","arg":{"op":"var"}},{"op":"log","arg":{"op":"var"}}]}}"#,
+            ),
+        ];
+
+        for case in cases {
+            let expression: Expression = case.0.parse().unwrap();
+            assert_eq!(expression.to_json().to_string(), case.1);
+            assert_eq!(Expression::from_json_str(case.1), Ok(expression.clone()));
+            let derivative = expression.diff();
+            assert_eq!(Expression::from_json(&derivative.to_json()), Ok(derivative));
+        }
+    }
+
+    #[test]
+    fn test_json_undefined() {
+        let expression = sin(apply("f", [x(), pow(x(), 2)])).diff().diff();
+        assert_eq!(Expression::from_json(&expression.to_json()), Ok(expression));
+
+        let derivative = Expression::from(Derivative::new(apply("f", [x()]), 2, 0).unwrap());
+        assert_eq!(
+            derivative.to_json().to_string(),
+            r#"{"version":1,"expression":{"op":"derivative","func":{"op":"apply","name":"f","args":[{"op":"var"}]},"order":2,"wrt":0}}"#
+        );
+        let negative = r#"{"version":1,"expression":{"op":"derivative","func":{"op":"var"},"order":-1,"wrt":0}}"#;
+        assert_eq!(
+            Expression::from_json_str(negative),
+            Err(SchemaError {
+                path: "$.expression.order".to_string(),
+                message: "integer out of range".to_string(),
+            })
+        );
+        let cases = [
+            (
+                r#"{"version":1,"expression":{"op":"derivative","func":{"op":"var"},"order":1,"wrt":0}}"#,
+                "the derivative of `x`, which is not a function at $.expression",
+            ),
+            (
+                r#"{"version":1,"expression":{"op":"derivative","func":{"op":"apply","name":"f","args":[{"op":"var"}]},"order":1,"wrt":1}}"#,
+                "the derivative of `f` with respect to argument 1, which it does not have at $.expression",
+            ),
+        ];
+        for case in cases {
+            let error = Expression::from_json_str(case.0).unwrap_err();
+            assert_eq!(error.to_string(), case.1);
+        }
+    }
+
+    #[test]
+    fn test_from_json_error() {
+        let cases = [
+            (r#"[1]"#, "$", "expected an object"),
+            (
+                r#"{"expression":{"op":"var"}}"#,
+                "$",
+                "missing member `version`",
+            ),
+            (
+                r#"{"version":2,"expression":{"op":"var"}}"#,
+                "$.version",
+                "unsupported version 2",
+            ),
+            (
+                r#"{"version":1,"expression":{"op":"var"},"extra":0}"#,
+                "$.extra",
+                "unexpected member `extra`",
+            ),
+            (
+                r#"{"version":1,"expression":{"op":"add","terms":[{"op":"var"},{"op":"sinh","arg":{"op":"var"}}]}}"#,
+                "$.expression.terms[1].op",
+                "unknown op `sinh`",
+            ),
+            (
+                r#"{"version":1,"expression":{"op":"pow","exp":1.5,"arg":{"op":"var"}}}"#,
+                "$.expression.exp",
+                "expected an integer",
+            ),
+            (
+                r#"{"version":1,"expression":{"op":"sin"}}"#,
+                "$.expression",
+                "missing member `arg`",
+            ),
+            (
+                r#"{"version":1,"expression":{"op":"var","arg":{"op":"var"}}}"#,
+                "$.expression.arg",
+                "unexpected member `arg`",
+            ),
+            (
+                r#"{"version":1,"expression":{"op":"var","op":"sin"}}"#,
+                "$.expression",
+                "duplicate member `op`",
+            ),
+            (
+                r#"{"version":1,"expression":{"op":"var"},"version":1}"#,
+                "$",
+                "duplicate member `version`",
+            ),
+            (
+                r#"{"version":1,"expression":{"value":1}}"#,
+                "$.expression",
+                "missing member `op`",
+            ),
+            (
+                r#"{"version":1,"expression":{"op":"mul","factors":{}}}"#,
+                "$.expression.factors",
+                "expected an array",
+            ),
+            (
+                r#"{"version":1,"expression":"x"}"#,
+                "$.expression",
+                "expected an object",
+            ),
+            (
+                r#"{"version":1,"#,
+                "$",
+                "expected a member name at column 14",
+            ),
+        ];
+
+        for case in cases {
+            assert_eq!(
+                Expression::from_json_str(case.0),
+                Err(SchemaError {
+                    path: case.1.to_string(),
+                    message: case.2.to_string(),
+                }),
+                "{}",
+                case.0
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_schema() {
+        assert!(parse(JSON_SCHEMA).is_ok());
+    }
+}
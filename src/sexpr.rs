@@ -0,0 +1,356 @@
+use std::fmt::Write;
+
+use crate::function::lookup;
+use crate::parse::ParseError;
+use crate::{Apply, Base, Cos, Custom, Derivative, Exp, Expression, Log, Pow, Scaler, Sin, Tan, X};
+
+impl Expression {
+    /// Writes the tree as an s-expression such as `(mul (exp x) (add 1 0))`.
+    /// Unlike `Display`, this keeps every node, so `from_sexpr` gives back an
+    /// identical tree.
+    pub fn to_sexpr(&self) -> String {
+        let mut sexpr = String::new();
+        write_expression(self, &mut sexpr);
+        sexpr
+    }
+
+    /// Reads the format written by `to_sexpr`.
+    pub fn from_sexpr(input: &str) -> Result<Expression, ParseError> {
+        let mut parser = Parser { input, position: 0 };
+        let expression = parser.expression()?;
+        parser.whitespace();
+        match parser.peek() {
+            None => Ok(expression),
+            Some(')') => Err(parser.error("unmatched `)`")),
+            Some(_) => Err(parser.error("expected end of input")),
+        }
+    }
+}
+
+fn write_expression(expression: &Expression, sexpr: &mut String) {
+    match expression {
+        Expression::Base(base) => write_base(base, sexpr),
+        Expression::Add(add) => write_list("add", add, sexpr),
+        Expression::Mul(mul) => write_list("mul", mul, sexpr),
+    }
+}
+
+fn write_base(base: &Base, sexpr: &mut String) {
+    match base {
+        Base::Scaler(scaler) => {
+            let _ = write!(sexpr, "{}", scaler);
+        }
+        Base::X(_) => sexpr.push('x'),
+        Base::Exp(exp) => write_list("exp", [&*exp.arg], sexpr),
+        Base::Pow(pow) => {
+            sexpr.push_str("(pow ");
+            write_expression(&pow.arg, sexpr);
+            let _ = write!(sexpr, " {})", pow.exp);
+        }
+        Base::Log(log) => write_list("log", [&*log.arg], sexpr),
+        Base::Sin(sin) => write_list("sin", [&*sin.arg], sexpr),
+        Base::Cos(cos) => write_list("cos", [&*cos.arg], sexpr),
+        Base::Tan(tan) => write_list("tan", [&*tan.arg], sexpr),
+        Base::Custom(custom) => write_list(&custom.name, [&*custom.arg], sexpr),
+        Base::Apply(apply) => write_list(&format!("apply {}", apply.name), &apply.args, sexpr),
+        Base::Derivative(derivative) => {
+            sexpr.push_str("(derivative ");
+            write_expression(&derivative.func, sexpr);
+            let _ = write!(sexpr, " {} {})", derivative.order, derivative.wrt);
+        }
+    }
+}
+
+fn write_list<'a>(
+    operator: &str,
+    operands: impl IntoIterator<Item = &'a Expression>,
+    sexpr: &mut String,
+) {
+    sexpr.push('(');
+    sexpr.push_str(operator);
+    for operand in operands {
+        sexpr.push(' ');
+        write_expression(operand, sexpr);
+    }
+    sexpr.push(')');
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            message: message.to_string(),
+            position: self.position,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.position += c.len_utf8();
+        }
+    }
+
+    // A symbol or integer, up to the next parenthesis or whitespace.
+    fn atom(&mut self) -> &str {
+        let start = self.position;
+        while let Some(c) = self
+            .peek()
+            .filter(|c| !c.is_whitespace() && *c != '(' && *c != ')')
+        {
+            self.position += c.len_utf8();
+        }
+        &self.input[start..self.position]
+    }
+
+    fn expression(&mut self) -> Result<Expression, ParseError> {
+        self.whitespace();
+        let start = self.position;
+        match self.peek() {
+            None => Err(self.error("unexpected end of input")),
+            Some(')') => Err(self.error("unmatched `)`")),
+            Some('(') => self.list(),
+            Some(_) => match self.atom() {
+                "x" => Ok(Expression::Base(Base::X(X {}))),
+                atom => match atom.parse::<Scaler>() {
+                    Ok(scaler) => Ok(Expression::Base(Base::Scaler(scaler))),
+                    Err(_) => Err(ParseError {
+                        message: format!("unknown symbol `{}`", atom),
+                        position: start,
+                    }),
+                },
+            },
+        }
+    }
+
+    fn list(&mut self) -> Result<Expression, ParseError> {
+        let open = self.position;
+        self.position += 1;
+        self.whitespace();
+        let operator_position = self.position;
+        let operator = self.atom().to_string();
+        let mut operands = vec![];
+        let mut exponent = None;
+        let mut name = None;
+        let mut indices = vec![];
+        loop {
+            self.whitespace();
+            match self.peek() {
+                Some(')') => {
+                    self.position += 1;
+                    break;
+                }
+                None => {
+                    return Err(ParseError {
+                        message: "unclosed `(`".to_string(),
+                        position: open,
+                    })
+                }
+                // The exponent of `(pow arg n)` is a plain integer.
+                Some(_) if operator == "pow" && operands.len() == 1 && exponent.is_none() => {
+                    let start = self.position;
+                    let atom = self.atom();
+                    exponent = Some(atom.parse::<isize>().map_err(|_| ParseError {
+                        message: "exponent must be an integer".to_string(),
+                        position: start,
+                    })?);
+                }
+                // The function name of `(apply f args...)` is a plain symbol.
+                Some(c) if operator == "apply" && name.is_none() => {
+                    if c == '(' {
+                        return Err(self.error("expected a function name"));
+                    }
+                    name = Some(self.atom().to_string());
+                }
+                // The order and argument of `(derivative func n i)` are plain
+                // integers.
+                Some(_) if operator == "derivative" && operands.len() == 1 && indices.len() < 2 => {
+                    let start = self.position;
+                    let atom = self.atom();
+                    indices.push(atom.parse::<usize>().map_err(|_| ParseError {
+                        message: "order and argument must be non-negative integers".to_string(),
+                        position: start,
+                    })?);
+                }
+                Some(_) => operands.push(self.expression()?),
+            }
+        }
+
+        let arity = |n: usize| {
+            let count = operands.len() + usize::from(exponent.is_some()) + indices.len();
+            if count == n {
+                Ok(())
+            } else {
+                Err(ParseError {
+                    message: format!(
+                        "`{}` takes {} operand{}",
+                        operator,
+                        n,
+                        if n == 1 { "" } else { "s" }
+                    ),
+                    position: open,
+                })
+            }
+        };
+        let base = match operator.as_str() {
+            "add" => return Ok(Expression::Add(operands)),
+            "mul" => return Ok(Expression::Mul(operands)),
+            "pow" => {
+                arity(2)?;
+                return Ok(Expression::Base(Base::Pow(Pow {
+                    exp: exponent.unwrap_or_default(),
+                    arg: Box::new(operands.remove(0)),
+                })));
+            }
+            "apply" => match name {
+                Some(name) => Base::Apply(Apply {
+                    name,
+                    args: operands,
+                }),
+                None => return Err(self.error("expected a function name")),
+            },
+            "derivative" => {
+                arity(3)?;
+                match Derivative::new(operands.remove(0), indices[0], indices[1]) {
+                    Ok(derivative) => Base::Derivative(derivative),
+                    Err(message) => {
+                        return Err(ParseError {
+                            message,
+                            position: open,
+                        })
+                    }
+                }
+            }
+            "exp" | "log" | "sin" | "cos" | "tan" => {
+                arity(1)?;
+                let arg = Box::new(operands.remove(0));
+                match operator.as_str() {
+                    "exp" => Base::Exp(Exp { arg }),
+                    "log" => Base::Log(Log { arg }),
+                    "sin" => Base::Sin(Sin { arg }),
+                    "cos" => Base::Cos(Cos { arg }),
+                    _ => Base::Tan(Tan { arg }),
+                }
+            }
+            name if lookup(name).is_some() => {
+                arity(1)?;
+                Base::Custom(Custom {
+                    name: name.to_string(),
+                    arg: Box::new(operands.remove(0)),
+                })
+            }
+            "" => {
+                return Err(ParseError {
+                    message: "expected an operator after `(`".to_string(),
+                    position: operator_position,
+                })
+            }
+            operator => {
+                return Err(ParseError {
+                    message: format!("unknown operator `{}`", operator),
+                    position: operator_position,
+                })
+            }
+        };
+        Ok(Expression::Base(base))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::{parse, ParseError};
+    use crate::{Base, Expression, Pow, X};
+
+    #[test]
+    fn test_sexpr() {
+        let cases = [
+            ("3", "3"),
+            ("-x", "(mul -1 x)"),
+            ("exp(x) * (1 + 0)", "(mul (exp x) (add 1 0))"),
+            (
+                "sin(x)^-2 - log(x)",
+                "(add (pow (sin x) -2) (mul -1 (log x)))",
+            ),
+            ("cos(tan(x))", "(cos (tan x))"),
+        ];
+
+        for case in cases {
+            let expression = parse(case.0).unwrap();
+            assert_eq!(expression.to_sexpr(), case.1);
+            assert_eq!(Expression::from_sexpr(case.1), Ok(expression));
+        }
+    }
+
+    #[test]
+    fn test_sexpr_lossless() {
+        let cases = [
+            Expression::Add(vec![]),
+            Expression::Mul(vec![Expression::Add(vec![Expression::Base(Base::X(X {}))])]),
+            Expression::Base(Base::Pow(Pow {
+                exp: 0,
+                arg: Box::new(Expression::Base(Base::Scaler(isize::MIN))),
+            })),
+        ];
+
+        for case in cases {
+            assert_eq!(Expression::from_sexpr(&case.to_sexpr()), Ok(case));
+        }
+        assert_eq!(
+            Expression::from_sexpr(" ( add\n 1\t(mul) ) "),
+            Ok(Expression::Add(vec![
+                Expression::Base(Base::Scaler(1)),
+                Expression::Mul(vec![]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_from_sexpr_error() {
+        let cases = [
+            ("", "unexpected end of input", 0),
+            ("(add 1", "unclosed `(`", 0),
+            ("x)", "unmatched `)`", 1),
+            ("x x", "expected end of input", 2),
+            ("(sin x x)", "`sin` takes 1 operand", 0),
+            ("(pow x)", "`pow` takes 2 operands", 0),
+            ("(pow x y)", "exponent must be an integer", 7),
+            ("(sinh x)", "unknown operator `sinh`", 1),
+            ("(add y)", "unknown symbol `y`", 5),
+            ("()", "expected an operator after `(`", 1),
+            (
+                "(derivative x 1 0)",
+                "the derivative of `x`, which is not a function",
+                0,
+            ),
+            (
+                "(add (derivative (apply f x) 1 5))",
+                "the derivative of `f` with respect to argument 5, which it does not have",
+                5,
+            ),
+            (
+                "(derivative (derivative (apply f x) 18446744073709551615 0) 1 0)",
+                "the derivative of `f` with respect to argument 0 of an order above 18446744073709551615",
+                0,
+            ),
+        ];
+
+        for case in cases {
+            assert_eq!(
+                Expression::from_sexpr(case.0),
+                Err(ParseError {
+                    message: case.1.to_string(),
+                    position: case.2,
+                }),
+                "{}",
+                case.0
+            );
+        }
+    }
+}
@@ -0,0 +1,544 @@
+use std::collections::BTreeMap;
+
+use crate::{Apply, Base, Cos, Custom, Derivative, Exp, Expression, Log, Pow, Sin, Tan};
+
+impl Expression {
+    /// Rewrites the expression into a canonical form: nested sums and
+    /// products are flattened, integer constants are folded, like terms and
+    /// like factors are collected and the remaining operands are sorted.
+    /// Products of sums are not expanded.
+    pub fn simplify(&self) -> Expression {
+        match self {
+            Self::Base(base) => base.simplify(),
+            Self::Add(add) => sum(add.iter().map(|e| e.simplify()).collect()),
+            Self::Mul(mul) => product(mul.iter().map(|e| e.simplify()).collect()),
+        }
+    }
+
+    /// Like `simplify`, but also multiplies out products of sums and positive
+    /// powers of sums, so that expressions which only differ by the
+    /// distributive law end up identical.
+    pub fn expand(&self) -> Expression {
+        expand(self.simplify())
+    }
+}
+
+impl Base {
+    fn simplify(&self) -> Expression {
+        match self {
+            Self::Scaler(_) | Self::X(_) => Expression::Base(self.clone()),
+            Self::Exp(exp) => match exp.arg.simplify() {
+                Expression::Base(Base::Scaler(0)) => Expression::Base(Base::Scaler(1)),
+                arg => Expression::Base(Base::Exp(Exp { arg: Box::new(arg) })),
+            },
+            Self::Pow(pow) => power(pow.arg.simplify(), pow.exp),
+            Self::Log(log) => match log.arg.simplify() {
+                Expression::Base(Base::Scaler(1)) => Expression::Base(Base::Scaler(0)),
+                arg => Expression::Base(Base::Log(Log { arg: Box::new(arg) })),
+            },
+            Self::Sin(sin) => match sin.arg.simplify() {
+                Expression::Base(Base::Scaler(0)) => Expression::Base(Base::Scaler(0)),
+                arg => Expression::Base(Base::Sin(Sin { arg: Box::new(arg) })),
+            },
+            Self::Cos(cos) => match cos.arg.simplify() {
+                Expression::Base(Base::Scaler(0)) => Expression::Base(Base::Scaler(1)),
+                arg => Expression::Base(Base::Cos(Cos { arg: Box::new(arg) })),
+            },
+            Self::Tan(tan) => match tan.arg.simplify() {
+                Expression::Base(Base::Scaler(0)) => Expression::Base(Base::Scaler(0)),
+                arg => Expression::Base(Base::Tan(Tan { arg: Box::new(arg) })),
+            },
+            Self::Custom(custom) => Expression::Base(Base::Custom(Custom {
+                name: custom.name.clone(),
+                arg: Box::new(custom.arg.simplify()),
+            })),
+            Self::Apply(apply) => Expression::Base(Base::Apply(Apply {
+                name: apply.name.clone(),
+                args: apply.args.iter().map(|e| e.simplify()).collect(),
+            })),
+            Self::Derivative(derivative) => Expression::Base(Base::Derivative(Derivative {
+                func: Box::new(derivative.func.simplify()),
+                order: derivative.order,
+                wrt: derivative.wrt,
+            })),
+        }
+    }
+}
+
+// Powers of sums above this are left alone rather than multiplied out.
+const MAX_EXPANDED_POWER: isize = 64;
+
+// Products and powers of sums that would expand into more terms than this are
+// left alone as well.
+pub(crate) const MAX_EXPANDED_TERMS: usize = 4096;
+
+/// An upper bound on the number of terms in the expansion of `expression`,
+/// saturating at `usize::MAX`. Function arguments are not counted.
+pub(crate) fn expanded_terms(expression: &Expression) -> usize {
+    match expression {
+        Expression::Add(add) => add
+            .iter()
+            .map(expanded_terms)
+            .fold(0, usize::saturating_add),
+        Expression::Mul(mul) => mul
+            .iter()
+            .map(expanded_terms)
+            .fold(1, usize::saturating_mul),
+        Expression::Base(Base::Pow(pow)) if pow.exp > 1 => {
+            power_terms(expanded_terms(&pow.arg), pow.exp as usize)
+        }
+        _ => 1,
+    }
+}
+
+// There are `(exp + n - 1) choose (n - 1)` monomials of degree `exp` in `n`
+// terms. Counts above `MAX_EXPANDED_TERMS` are reported as `usize::MAX`.
+fn power_terms(n: usize, exp: usize) -> usize {
+    if n > MAX_EXPANDED_TERMS {
+        return usize::MAX;
+    }
+    let mut count: usize = 1;
+    for k in 1..n {
+        count = count * exp.saturating_add(k) / k;
+        if count > MAX_EXPANDED_TERMS {
+            return usize::MAX;
+        }
+    }
+    count
+}
+
+// `expression` must already be simplified.
+fn expand(expression: Expression) -> Expression {
+    match expression {
+        Expression::Add(add) => sum(add.into_iter().map(expand).collect()),
+        Expression::Mul(mul) => distribute(mul.into_iter().map(expand).collect()),
+        Expression::Base(Base::Pow(pow)) => match expand(*pow.arg) {
+            Expression::Add(add)
+                if pow.exp > 1
+                    && pow.exp <= MAX_EXPANDED_POWER
+                    && power_terms(add.len(), pow.exp as usize) <= MAX_EXPANDED_TERMS =>
+            {
+                distribute(vec![Expression::Add(add); pow.exp as usize])
+            }
+            arg => power(arg, pow.exp),
+        },
+        Expression::Base(base) => Expression::Base(match base {
+            Base::Exp(exp) => Base::Exp(Exp {
+                arg: Box::new(expand(*exp.arg)),
+            }),
+            Base::Log(log) => Base::Log(Log {
+                arg: Box::new(expand(*log.arg)),
+            }),
+            Base::Sin(sin) => Base::Sin(Sin {
+                arg: Box::new(expand(*sin.arg)),
+            }),
+            Base::Cos(cos) => Base::Cos(Cos {
+                arg: Box::new(expand(*cos.arg)),
+            }),
+            Base::Tan(tan) => Base::Tan(Tan {
+                arg: Box::new(expand(*tan.arg)),
+            }),
+            Base::Custom(custom) => Base::Custom(Custom {
+                name: custom.name,
+                arg: Box::new(expand(*custom.arg)),
+            }),
+            Base::Apply(apply) => Base::Apply(Apply {
+                name: apply.name,
+                args: apply.args.into_iter().map(expand).collect(),
+            }),
+            Base::Derivative(derivative) => Base::Derivative(Derivative {
+                func: Box::new(expand(*derivative.func)),
+                order: derivative.order,
+                wrt: derivative.wrt,
+            }),
+            base => base,
+        })
+        .simplify(),
+    }
+}
+
+// Multiplies in one factor at a time and collects like terms after each
+// step, so that `(x + 1)^n` never has more than `n + 1` terms in flight.
+// `factors` must already be expanded.
+fn distribute(factors: Vec<Expression>) -> Expression {
+    let mut terms = vec![Expression::Base(Base::Scaler(1))];
+    for (i, factor) in factors.iter().enumerate() {
+        let operands = match factor {
+            Expression::Add(add) => add.as_slice(),
+            factor => std::slice::from_ref(factor),
+        };
+        if terms.len().saturating_mul(operands.len()) > MAX_EXPANDED_TERMS {
+            let mut rest = vec![sum(terms)];
+            rest.extend(factors[i..].iter().cloned());
+            return product(rest);
+        }
+        let expanded = sum(terms
+            .iter()
+            .flat_map(|term| {
+                operands
+                    .iter()
+                    .map(move |e| product(vec![term.clone(), e.clone()]))
+            })
+            .collect());
+        terms = match expanded {
+            Expression::Add(add) => add,
+            term => vec![term],
+        };
+    }
+    sum(terms)
+}
+
+// `base` must already be simplified.
+fn power(base: Expression, exp: isize) -> Expression {
+    match (base, exp) {
+        (_, 0) => Expression::Base(Base::Scaler(1)),
+        (base, 1) => base,
+        (Expression::Base(Base::Scaler(scaler)), exp) if exp > 0 => {
+            match u32::try_from(exp).ok().and_then(|e| scaler.checked_pow(e)) {
+                Some(value) => Expression::Base(Base::Scaler(value)),
+                None => pow(Expression::Base(Base::Scaler(scaler)), exp),
+            }
+        }
+        (Expression::Base(Base::Pow(inner)), exp) => match inner.exp.checked_mul(exp) {
+            Some(exp) => power(*inner.arg, exp),
+            None => pow(Expression::Base(Base::Pow(inner)), exp),
+        },
+        (Expression::Mul(mul), exp) => product(mul.into_iter().map(|e| power(e, exp)).collect()),
+        (base, exp) => pow(base, exp),
+    }
+}
+
+fn pow(base: Expression, exp: isize) -> Expression {
+    Expression::Base(Base::Pow(Pow {
+        exp,
+        arg: Box::new(base),
+    }))
+}
+
+// `factors` must already be simplified.
+fn product(factors: Vec<Expression>) -> Expression {
+    let mut coefficient: isize = 1;
+    let mut powers: BTreeMap<Expression, isize> = BTreeMap::new();
+    let mut overflowed = vec![];
+    let mut stack = factors;
+    while let Some(factor) = stack.pop() {
+        match factor {
+            Expression::Mul(mul) => stack.extend(mul),
+            Expression::Base(Base::Scaler(scaler)) => match coefficient.checked_mul(scaler) {
+                Some(value) => coefficient = value,
+                None => overflowed.push(Expression::Base(Base::Scaler(scaler))),
+            },
+            Expression::Base(Base::Pow(pow)) => {
+                let exp = powers.entry(*pow.arg.clone()).or_insert(0);
+                match exp.checked_add(pow.exp) {
+                    Some(value) => *exp = value,
+                    None => overflowed.push(Expression::Base(Base::Pow(pow))),
+                }
+            }
+            factor => {
+                let exp = powers.entry(factor.clone()).or_insert(0);
+                match exp.checked_add(1) {
+                    Some(value) => *exp = value,
+                    None => overflowed.push(factor),
+                }
+            }
+        }
+    }
+    if coefficient == 0 {
+        return Expression::Base(Base::Scaler(0));
+    }
+
+    // Cancel integer denominators such as the `2^-1` in `4 * 2^-1`.
+    for (base, exp) in powers.iter_mut() {
+        if let Expression::Base(Base::Scaler(scaler)) = base {
+            while *exp < 0 && *scaler != 0 && coefficient.checked_rem(*scaler) == Some(0) {
+                coefficient /= scaler;
+                *exp += 1;
+            }
+        }
+    }
+
+    let mut result = vec![];
+    if coefficient != 1 {
+        result.push(Expression::Base(Base::Scaler(coefficient)));
+    }
+    for (base, exp) in powers {
+        match exp {
+            0 => {}
+            1 => result.push(base),
+            exp => result.push(pow(base, exp)),
+        }
+    }
+    result.extend(overflowed);
+    match result.len() {
+        0 => Expression::Base(Base::Scaler(1)),
+        1 => result.remove(0),
+        _ => Expression::Mul(result),
+    }
+}
+
+// `terms` must already be simplified.
+fn sum(terms: Vec<Expression>) -> Expression {
+    let mut constant: isize = 0;
+    let mut coefficients: BTreeMap<Expression, isize> = BTreeMap::new();
+    let mut overflowed = vec![];
+    let mut stack = terms;
+    while let Some(term) = stack.pop() {
+        match term {
+            Expression::Add(add) => stack.extend(add),
+            Expression::Base(Base::Scaler(scaler)) => match constant.checked_add(scaler) {
+                Some(value) => constant = value,
+                None => overflowed.push(Expression::Base(Base::Scaler(scaler))),
+            },
+            term => {
+                let (scaler, monomial) = split_coefficient(term);
+                let coefficient = coefficients.entry(monomial.clone()).or_insert(0);
+                match coefficient.checked_add(scaler) {
+                    Some(value) => *coefficient = value,
+                    None => overflowed.push(product(vec![
+                        Expression::Base(Base::Scaler(scaler)),
+                        monomial,
+                    ])),
+                }
+            }
+        }
+    }
+
+    let mut result = vec![];
+    for (monomial, coefficient) in coefficients {
+        match coefficient {
+            0 => {}
+            1 => result.push(monomial),
+            coefficient => result.push(product(vec![
+                Expression::Base(Base::Scaler(coefficient)),
+                monomial,
+            ])),
+        }
+    }
+    if constant != 0 {
+        result.push(Expression::Base(Base::Scaler(constant)));
+    }
+    result.extend(overflowed);
+    match result.len() {
+        0 => Expression::Base(Base::Scaler(0)),
+        1 => result.remove(0),
+        _ => Expression::Add(result),
+    }
+}
+
+// Splits a simplified term such as `3 * x * sin(x)` into `(3, x * sin(x))`.
+fn split_coefficient(term: Expression) -> (isize, Expression) {
+    match term {
+        Expression::Mul(mut mul) => match mul.first() {
+            Some(Expression::Base(Base::Scaler(scaler))) => {
+                let scaler = *scaler;
+                mul.remove(0);
+                if mul.len() == 1 {
+                    (scaler, mul.remove(0))
+                } else {
+                    (scaler, Expression::Mul(mul))
+                }
+            }
+            _ => (1, Expression::Mul(mul)),
+        },
+        term => (1, term),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{cos, exp, pow, sin, tan, x};
+    use crate::{Base, Cos, Exp, Expression, Log, Pow, Sin, X};
+
+    #[test]
+    fn test_simplify() {
+        let cases = [
+            (
+                Expression::Add(vec![Expression::Mul(vec![Expression::Base(Base::Scaler(
+                    0,
+                ))])]),
+                Expression::Base(Base::Scaler(0)),
+            ),
+            (
+                Expression::Mul(vec![
+                    Expression::Base(Base::Scaler(1)),
+                    Expression::Base(Base::Pow(Pow {
+                        exp: 0,
+                        arg: Box::new(Expression::Base(Base::X(X {}))),
+                    })),
+                    Expression::Base(Base::Scaler(1)),
+                ]),
+                Expression::Base(Base::Scaler(1)),
+            ),
+            (
+                Expression::Mul(vec![
+                    Expression::Base(Base::X(X {})),
+                    Expression::Base(Base::Scaler(2)),
+                ]),
+                Expression::Mul(vec![
+                    Expression::Base(Base::Scaler(2)),
+                    Expression::Base(Base::X(X {})),
+                ]),
+            ),
+            (
+                Expression::Add(vec![
+                    Expression::Mul(vec![
+                        Expression::Base(Base::Scaler(2)),
+                        Expression::Base(Base::X(X {})),
+                    ]),
+                    Expression::Base(Base::Scaler(1)),
+                    Expression::Mul(vec![
+                        Expression::Base(Base::X(X {})),
+                        Expression::Base(Base::Scaler(3)),
+                    ]),
+                    Expression::Base(Base::Scaler(-1)),
+                ]),
+                Expression::Mul(vec![
+                    Expression::Base(Base::Scaler(5)),
+                    Expression::Base(Base::X(X {})),
+                ]),
+            ),
+            (
+                Expression::Mul(vec![
+                    Expression::Base(Base::X(X {})),
+                    Expression::Base(Base::Pow(Pow {
+                        exp: 2,
+                        arg: Box::new(Expression::Base(Base::X(X {}))),
+                    })),
+                    Expression::Base(Base::Pow(Pow {
+                        exp: -3,
+                        arg: Box::new(Expression::Base(Base::X(X {}))),
+                    })),
+                ]),
+                Expression::Base(Base::Scaler(1)),
+            ),
+            (
+                Expression::Base(Base::Pow(Pow {
+                    exp: 2,
+                    arg: Box::new(Expression::Mul(vec![
+                        Expression::Base(Base::Scaler(3)),
+                        Expression::Base(Base::Pow(Pow {
+                            exp: 2,
+                            arg: Box::new(Expression::Base(Base::X(X {}))),
+                        })),
+                    ])),
+                })),
+                Expression::Mul(vec![
+                    Expression::Base(Base::Scaler(9)),
+                    Expression::Base(Base::Pow(Pow {
+                        exp: 4,
+                        arg: Box::new(Expression::Base(Base::X(X {}))),
+                    })),
+                ]),
+            ),
+            (
+                Expression::Mul(vec![
+                    Expression::Base(Base::Scaler(4)),
+                    Expression::Base(Base::Pow(Pow {
+                        exp: -1,
+                        arg: Box::new(Expression::Base(Base::Scaler(2))),
+                    })),
+                ]),
+                Expression::Base(Base::Scaler(2)),
+            ),
+            (
+                Expression::Add(vec![
+                    Expression::Base(Base::Exp(Exp {
+                        arg: Box::new(Expression::Base(Base::Scaler(0))),
+                    })),
+                    Expression::Base(Base::Log(Log {
+                        arg: Box::new(Expression::Base(Base::Scaler(1))),
+                    })),
+                    Expression::Base(Base::Sin(Sin {
+                        arg: Box::new(Expression::Base(Base::Scaler(0))),
+                    })),
+                    Expression::Base(Base::Cos(Cos {
+                        arg: Box::new(Expression::Add(vec![
+                            Expression::Base(Base::X(X {})),
+                            Expression::Mul(vec![
+                                Expression::Base(Base::Scaler(-1)),
+                                Expression::Base(Base::X(X {})),
+                            ]),
+                        ])),
+                    })),
+                ]),
+                Expression::Base(Base::Scaler(2)),
+            ),
+        ];
+
+        for case in cases {
+            assert_eq!(case.0.simplify(), case.1);
+            assert_eq!(case.1.simplify(), case.1);
+        }
+    }
+
+    #[test]
+    fn test_expand() {
+        let cases = [
+            (
+                Expression::Mul(vec![
+                    Expression::Add(vec![
+                        Expression::Base(Base::X(X {})),
+                        Expression::Base(Base::Scaler(1)),
+                    ]),
+                    Expression::Add(vec![
+                        Expression::Base(Base::X(X {})),
+                        Expression::Base(Base::Scaler(-1)),
+                    ]),
+                ]),
+                Expression::Add(vec![
+                    Expression::Base(Base::Pow(Pow {
+                        exp: 2,
+                        arg: Box::new(Expression::Base(Base::X(X {}))),
+                    })),
+                    Expression::Base(Base::Scaler(-1)),
+                ]),
+            ),
+            (
+                Expression::Base(Base::Pow(Pow {
+                    exp: 2,
+                    arg: Box::new(Expression::Add(vec![
+                        Expression::Base(Base::X(X {})),
+                        Expression::Base(Base::Scaler(1)),
+                    ])),
+                })),
+                Expression::Add(vec![
+                    Expression::Mul(vec![
+                        Expression::Base(Base::Scaler(2)),
+                        Expression::Base(Base::X(X {})),
+                    ]),
+                    Expression::Base(Base::Pow(Pow {
+                        exp: 2,
+                        arg: Box::new(Expression::Base(Base::X(X {}))),
+                    })),
+                    Expression::Base(Base::Scaler(1)),
+                ]),
+            ),
+        ];
+
+        for case in cases {
+            assert_eq!(case.0.expand(), case.1);
+            assert_eq!(case.1.expand(), case.1);
+        }
+    }
+
+    #[test]
+    fn test_expand_large() {
+        let cases = [
+            (pow(x() + 1, 64), 65),
+            (pow(x() + 1, 65), 1),
+            (pow(sin(x()) + cos(x()) + tan(x()) + exp(x()) + 1, 64), 1),
+            (pow(x() + 1, 40) * pow(x() - 1, 24), 65),
+        ];
+
+        for case in cases {
+            let terms = match case.0.expand() {
+                Expression::Add(add) => add.len(),
+                _ => 1,
+            };
+            assert_eq!(terms, case.1, "{}", case.0);
+        }
+        let coefficient = Expression::from(isize::MIN) * pow(-1, -1);
+        assert_eq!(coefficient.simplify(), coefficient.simplify().simplify());
+    }
+}
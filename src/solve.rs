@@ -0,0 +1,193 @@
+use std::fmt;
+
+use crate::compile::DomainError;
+use crate::Expression;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewtonOptions {
+    /// Converged once `|f(x)|` is at most `tolerance`.
+    pub tolerance: f64,
+    pub max_iterations: usize,
+    /// Fraction of the full Newton step that is taken, in `(0, 1]`. Other
+    /// values fail with `NewtonError::InvalidDamping`.
+    pub damping: f64,
+    /// Iterates with a magnitude above this are considered divergent.
+    pub divergence_limit: f64,
+}
+
+impl Default for NewtonOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 1e-12,
+            max_iterations: 50,
+            damping: 1.0,
+            divergence_limit: 1e12,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewtonStep {
+    pub x: f64,
+    pub fx: f64,
+    pub dfx: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NewtonError {
+    ZeroDerivative { x: f64 },
+    Diverged { x: f64 },
+    Domain(DomainError),
+    MaxIterations,
+    InvalidDamping(f64),
+}
+
+impl fmt::Display for NewtonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ZeroDerivative { x } => write!(f, "derivative is zero at {}", x),
+            Self::Diverged { x } => write!(f, "iteration diverged at {}", x),
+            Self::Domain(error) => write!(f, "{}", error),
+            Self::MaxIterations => write!(f, "did not converge within the iteration limit"),
+            Self::InvalidDamping(damping) => write!(f, "damping {} is outside (0, 1]", damping),
+        }
+    }
+}
+
+impl std::error::Error for NewtonError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewtonReport {
+    pub root: Result<f64, NewtonError>,
+    pub history: Vec<NewtonStep>,
+}
+
+impl NewtonReport {
+    pub fn converged(&self) -> bool {
+        self.root.is_ok()
+    }
+}
+
+pub fn newton(expression: &Expression, x0: f64, options: &NewtonOptions) -> NewtonReport {
+    let mut history = vec![];
+    if !(options.damping > 0.0 && options.damping <= 1.0) {
+        return NewtonReport {
+            root: Err(NewtonError::InvalidDamping(options.damping)),
+            history,
+        };
+    }
+    let f = expression.compile();
+    let df = expression.diff().compile();
+    let mut x = x0;
+    let root = loop {
+        if history.len() >= options.max_iterations {
+            break Err(NewtonError::MaxIterations);
+        }
+        let (fx, dfx) = match f.call(x).and_then(|fx| Ok((fx, df.call(x)?))) {
+            Ok(values) => values,
+            Err(error) => break Err(NewtonError::Domain(error)),
+        };
+        history.push(NewtonStep { x, fx, dfx });
+        if fx.abs() <= options.tolerance {
+            break Ok(x);
+        }
+        if dfx == 0.0 {
+            break Err(NewtonError::ZeroDerivative { x });
+        }
+        let step = options.damping * fx / dfx;
+        x -= step;
+        if !x.is_finite() || x.abs() > options.divergence_limit {
+            break Err(NewtonError::Diverged { x });
+        }
+    };
+    NewtonReport { root, history }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{cos, exp, log, pow, x};
+    use crate::compile::DomainError;
+    use crate::solve::{newton, NewtonError, NewtonOptions};
+
+    #[test]
+    fn test_newton() {
+        let cases = [
+            (pow(x(), 2) - 2, 1.0, Ok(2.0_f64.sqrt())),
+            (cos(x()), 0.0, Err(NewtonError::ZeroDerivative { x: 0.0 })),
+            (
+                log(x()),
+                -1.0,
+                Err(NewtonError::Domain(DomainError {
+                    function: "log",
+                    arg: -1.0,
+                })),
+            ),
+            (
+                pow(x(), -1),
+                1.0,
+                Err(NewtonError::Diverged {
+                    x: 2.0_f64.powi(40),
+                }),
+            ),
+        ];
+
+        for case in cases {
+            let report = newton(&case.0, case.1, &NewtonOptions::default());
+            match (report.root, case.2) {
+                (Ok(root), Ok(expected)) => assert!((root - expected).abs() < 1e-12),
+                (root, expected) => assert_eq!(root, expected),
+            }
+        }
+    }
+
+    #[test]
+    fn test_newton_options() {
+        let expression = pow(x(), 2) - 2;
+
+        let full = newton(&expression, 1.0, &NewtonOptions::default());
+        let damped = newton(
+            &expression,
+            1.0,
+            &NewtonOptions {
+                damping: 0.5,
+                ..NewtonOptions::default()
+            },
+        );
+        assert!(full.converged() && damped.converged());
+        assert!(damped.history.len() > full.history.len());
+        assert_eq!(full.history[0].x, 1.0);
+        assert_eq!(full.history[0].fx, -1.0);
+        assert_eq!(full.history[0].dfx, 2.0);
+
+        let limited = newton(
+            &expression,
+            1.0,
+            &NewtonOptions {
+                max_iterations: 2,
+                ..NewtonOptions::default()
+            },
+        );
+        assert_eq!(limited.root, Err(NewtonError::MaxIterations));
+        assert_eq!(limited.history.len(), 2);
+
+        for damping in [0.0, -0.5, 1.5, f64::NAN] {
+            let report = newton(
+                &expression,
+                1.0,
+                &NewtonOptions {
+                    damping,
+                    ..NewtonOptions::default()
+                },
+            );
+            assert!(matches!(report.root, Err(NewtonError::InvalidDamping(_))));
+            assert!(report.history.is_empty());
+        }
+
+        // The first step of `exp(10^13 * x) + 1` is below the tolerance, but
+        // there is no root.
+        let no_root = exp(10_000_000_000_000 * x()) + 1;
+        let report = newton(&no_root, 0.0, &NewtonOptions::default());
+        assert!(!report.converged());
+        assert!(report.history[1].x.abs() <= 1e-12);
+    }
+}
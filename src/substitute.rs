@@ -0,0 +1,165 @@
+use crate::visit::{fold_base, Fold};
+use crate::{Apply, Base, Cos, Custom, Derivative, Exp, Expression, Log, Pow, Sin, Tan, X};
+
+impl Expression {
+    /// Replaces every occurrence of the variable with `replacement`.
+    pub fn substitute(&self, variable: &X, replacement: &Expression) -> Expression {
+        match self {
+            Self::Base(base) => base.substitute(variable, replacement),
+            Self::Add(add) => Self::Add(
+                add.iter()
+                    .map(|e| e.substitute(variable, replacement))
+                    .collect(),
+            ),
+            Self::Mul(mul) => Self::Mul(
+                mul.iter()
+                    .map(|e| e.substitute(variable, replacement))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Base {
+    fn substitute(&self, variable: &X, replacement: &Expression) -> Expression {
+        let arg = |arg: &Expression| Box::new(arg.substitute(variable, replacement));
+        Expression::Base(match self {
+            Self::Scaler(scaler) => Self::Scaler(*scaler),
+            Self::X(_) => return replacement.clone(),
+            Self::Exp(exp) => Self::Exp(Exp { arg: arg(&exp.arg) }),
+            Self::Pow(pow) => Self::Pow(Pow {
+                exp: pow.exp,
+                arg: arg(&pow.arg),
+            }),
+            Self::Log(log) => Self::Log(Log { arg: arg(&log.arg) }),
+            Self::Sin(sin) => Self::Sin(Sin { arg: arg(&sin.arg) }),
+            Self::Cos(cos) => Self::Cos(Cos { arg: arg(&cos.arg) }),
+            Self::Tan(tan) => Self::Tan(Tan { arg: arg(&tan.arg) }),
+            Self::Custom(custom) => Self::Custom(Custom {
+                name: custom.name.clone(),
+                arg: arg(&custom.arg),
+            }),
+            Self::Apply(apply) => Self::Apply(Apply {
+                name: apply.name.clone(),
+                args: apply.args.iter().map(|e| *arg(e)).collect(),
+            }),
+            Self::Derivative(derivative) => Self::Derivative(Derivative {
+                func: arg(&derivative.func),
+                order: derivative.order,
+                wrt: derivative.wrt,
+            }),
+        })
+    }
+}
+
+impl Expression {
+    /// Gives the undefined function `name` a definition, an expression in `x`
+    /// where `x` stands for the argument. Calls `name(arg)` become the
+    /// definition at `arg` and their unevaluated derivatives are worked out.
+    /// Only calls with a single argument are replaced.
+    pub fn define(&self, name: &str, definition: &Expression) -> Expression {
+        Define { name, definition }.fold_expression(self)
+    }
+}
+
+struct Define<'a> {
+    name: &'a str,
+    definition: &'a Expression,
+}
+
+impl Fold for Define<'_> {
+    fn fold_base(&mut self, base: &Base) -> Expression {
+        let (apply, order) = match base {
+            Base::Apply(apply) => (apply, 0),
+            Base::Derivative(derivative) => match derivative.apply() {
+                Some(apply) => (apply, derivative.orders().iter().sum()),
+                None => return fold_base(self, base),
+            },
+            base => return fold_base(self, base),
+        };
+        if apply.name != self.name || apply.args.len() != 1 {
+            return fold_base(self, base);
+        }
+        let mut function = self.definition.clone();
+        for _ in 0..order {
+            function = function.diff();
+        }
+        function.substitute(&X {}, &self.fold_expression(&apply.args[0]))
+    }
+}
+
+/// Builds `outer(inner(x))`.
+pub fn compose(outer: &Expression, inner: &Expression) -> Expression {
+    outer.substitute(&X {}, inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{apply, exp, log, pow, sin, tan, x};
+    use crate::substitute::compose;
+    use crate::{Expression, X};
+
+    #[test]
+    fn test_substitute() {
+        let expression = x() * sin(x());
+        let replacement = 2 * x() + 1;
+
+        assert_eq!(
+            expression.substitute(&X {}, &replacement),
+            replacement.clone() * sin(replacement)
+        );
+    }
+
+    #[test]
+    fn test_compose_chain_rule() {
+        let functions = [
+            sin(x()),
+            pow(x(), 3),
+            log(x()),
+            x() * exp(x()),
+            2 * x() + 1,
+            tan(pow(x(), 2)),
+        ];
+
+        for outer in &functions {
+            for inner in &functions {
+                let expected =
+                    Expression::Mul(vec![outer.diff().substitute(&X {}, inner), inner.diff()]);
+                assert_eq!(compose(outer, inner).diff().expand(), expected.expand());
+            }
+        }
+    }
+
+    #[test]
+    fn test_define() {
+        let f = || apply("f", [x()]);
+        let definitions = [sin(x()), pow(x(), 3), x() * exp(x()), 2 * x() + 1];
+        let expressions = [
+            sin(f()),
+            f() * f(),
+            exp(apply("f", [pow(x(), 2)])),
+            apply("f", [apply("f", [x()])]),
+        ];
+
+        for definition in &definitions {
+            for expression in &expressions {
+                let defined = expression.define("f", definition);
+                let mut derivative = expression.clone();
+                let mut expected = defined.clone();
+                for _ in 0..2 {
+                    derivative = derivative.diff();
+                    expected = expected.diff();
+                    assert_eq!(
+                        derivative.define("f", definition).expand(),
+                        expected.expand(),
+                        "{}",
+                        derivative
+                    );
+                }
+            }
+        }
+
+        let untouched = apply("f", [x(), x()]) + apply("g", [x()]);
+        assert_eq!(untouched.define("f", &sin(x())), untouched);
+    }
+}
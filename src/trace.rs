@@ -0,0 +1,270 @@
+use std::fmt;
+
+use crate::latex::derivative_equation;
+use crate::visit::{fold_add, Fold};
+use crate::{diff_base, diff_mul, Base, Expression};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    Constant,
+    Variable,
+    Sum,
+    Product,
+    Power,
+    /// The chain rule through the named function.
+    Chain(String),
+    /// The chain rule through a function without a definition, which leaves
+    /// its derivative unevaluated.
+    Undefined,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Constant => write!(f, "constant rule"),
+            Self::Variable => write!(f, "variable rule"),
+            Self::Sum => write!(f, "sum rule"),
+            Self::Product => write!(f, "product rule"),
+            Self::Power => write!(f, "power rule"),
+            Self::Chain(function) => write!(f, "chain rule ({})", function),
+            Self::Undefined => write!(f, "chain rule (undefined function)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub expression: Expression,
+    pub rule: Rule,
+    pub result: Expression,
+    /// How many steps this one is nested in.
+    pub depth: usize,
+}
+
+/// The steps behind a derivative, outermost first: every step is followed by
+/// the steps for its sub-expressions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    pub derivative: Expression,
+    pub steps: Vec<Step>,
+}
+
+impl Expression {
+    /// Differentiates like `diff`, recording the rule applied at every node.
+    pub fn diff_with_trace(&self) -> Trace {
+        let mut tracer = Tracer {
+            depth: 0,
+            steps: vec![],
+        };
+        let derivative = tracer.fold_expression(self);
+        Trace {
+            derivative,
+            steps: tracer.steps,
+        }
+    }
+}
+
+impl Trace {
+    /// Renders the steps as LaTeX, one `\frac{d}{dx}` equation per line.
+    pub fn to_latex(&self) -> String {
+        let lines: Vec<String> = self
+            .steps
+            .iter()
+            .map(|step| {
+                format!(
+                    "{} && \\text{{{}}}",
+                    derivative_equation(&step.expression, &step.result, 1, "x"),
+                    step.rule
+                )
+            })
+            .collect();
+        format!(
+            "\\begin{{aligned}}\n{}\n\\end{{aligned}}",
+            lines.join(" \\\\\n")
+        )
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "{:indent$}{}: d/dx ",
+                "",
+                step.rule,
+                indent = 2 * step.depth
+            )?;
+            match step.expression {
+                Expression::Base(_) => write!(f, "{}", step.expression)?,
+                _ => write!(f, "({})", step.expression)?,
+            }
+            write!(f, " = {}", step.result)?;
+        }
+        Ok(())
+    }
+}
+
+// Wraps the rules of `Expression::diff`, recording a step in pre-order around
+// every node they differentiate.
+struct Tracer {
+    depth: usize,
+    steps: Vec<Step>,
+}
+
+impl Tracer {
+    fn record(
+        &mut self,
+        expression: Expression,
+        rule: Rule,
+        diff: impl FnOnce(&mut Self) -> Expression,
+    ) -> Expression {
+        let index = self.steps.len();
+        self.steps.push(Step {
+            expression,
+            rule,
+            result: Expression::Base(Base::Scaler(0)),
+            depth: self.depth,
+        });
+        self.depth += 1;
+        let result = diff(self);
+        self.depth -= 1;
+        self.steps[index].result = result.clone();
+        result
+    }
+}
+
+impl Fold for Tracer {
+    fn fold_add(&mut self, terms: &[Expression]) -> Expression {
+        let sum = Expression::Add(terms.to_vec());
+        self.record(sum, Rule::Sum, |tracer| fold_add(tracer, terms))
+    }
+
+    fn fold_mul(&mut self, factors: &[Expression]) -> Expression {
+        let product = Expression::Mul(factors.to_vec());
+        self.record(product, Rule::Product, |tracer| diff_mul(tracer, factors))
+    }
+
+    fn fold_base(&mut self, base: &Base) -> Expression {
+        let rule = match base {
+            Base::Scaler(_) => Rule::Constant,
+            Base::X(_) => Rule::Variable,
+            Base::Pow(_) => Rule::Power,
+            Base::Exp(_) => Rule::Chain("exp".to_string()),
+            Base::Log(_) => Rule::Chain("log".to_string()),
+            Base::Sin(_) => Rule::Chain("sin".to_string()),
+            Base::Cos(_) => Rule::Chain("cos".to_string()),
+            Base::Tan(_) => Rule::Chain("tan".to_string()),
+            Base::Custom(custom) => Rule::Chain(custom.name.clone()),
+            Base::Apply(_) | Base::Derivative(_) => Rule::Undefined,
+        };
+        let node = Expression::Base(base.clone());
+        self.record(node, rule, |tracer| diff_base(tracer, base))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{apply, custom, pow, sin, x};
+    use crate::parse::parse;
+    use crate::trace::Rule;
+
+    #[test]
+    fn test_diff_with_trace() {
+        let cases = [
+            "0",
+            "x",
+            "x^3 + 2x",
+            "sin(x^2) * exp(x)",
+            "log(cos(x)) - tan(x) / x",
+            "(x + 1) * (x - 1) * x",
+        ];
+
+        for case in cases {
+            let expression = parse(case).unwrap();
+            let trace = expression.diff_with_trace();
+            assert_eq!(trace.derivative, expression.diff(), "{}", case);
+            assert_eq!(trace.steps[0].expression, expression);
+            for step in &trace.steps {
+                assert_eq!(step.result, step.expression.diff(), "{}", case);
+            }
+        }
+    }
+
+    #[test]
+    fn test_trace_undefined() {
+        let expression = sin(apply("f", [x(), pow(x(), 2)]));
+        let trace = expression.diff_with_trace();
+
+        assert_eq!(trace.derivative, expression.diff());
+        assert_eq!(
+            trace
+                .steps
+                .iter()
+                .map(|step| step.rule.clone())
+                .collect::<Vec<_>>(),
+            [
+                Rule::Chain("sin".to_string()),
+                Rule::Undefined,
+                Rule::Variable,
+                Rule::Power,
+                Rule::Variable
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trace_unregistered() {
+        let expression = custom("unregistered", x());
+        let trace = expression.diff_with_trace();
+
+        assert_eq!(trace.derivative, expression.diff());
+        assert_eq!(
+            trace.to_string(),
+            "\
+chain rule (unregistered): d/dx unregistered(x) = unregistered'(x) * 1
+  variable rule: d/dx x = 1"
+        );
+    }
+
+    #[test]
+    fn test_trace_display() {
+        let trace = parse("sin(x^2) + 3").unwrap().diff_with_trace();
+
+        assert_eq!(
+            trace
+                .steps
+                .iter()
+                .map(|step| step.rule.clone())
+                .collect::<Vec<_>>(),
+            [
+                Rule::Sum,
+                Rule::Chain("sin".to_string()),
+                Rule::Power,
+                Rule::Variable,
+                Rule::Constant
+            ]
+        );
+        assert_eq!(
+            trace.to_string(),
+            "\
+sum rule: d/dx (sin(x^2) + 3) = cos(x^2) * 2 * x^1 * 1 + 0
+  chain rule (sin): d/dx sin(x^2) = cos(x^2) * 2 * x^1 * 1
+    power rule: d/dx x^2 = 2 * x^1 * 1
+      variable rule: d/dx x = 1
+  constant rule: d/dx 3 = 0"
+        );
+        assert_eq!(
+            parse("2x").unwrap().diff_with_trace().to_latex(),
+            "\
+\\begin{aligned}
+\\frac{d}{dx} \\left(2x\\right) = 0x + 2 \\cdot 1 && \\text{product rule} \\\\
+\\frac{d}{dx} 2 = 0 && \\text{constant rule} \\\\
+\\frac{d}{dx} x = 1 && \\text{variable rule}
+\\end{aligned}"
+        );
+    }
+}
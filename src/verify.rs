@@ -0,0 +1,146 @@
+use crate::rng::Rng;
+use crate::{Base, Expression};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyOptions {
+    pub samples: usize,
+    pub low: f64,
+    pub high: f64,
+    /// Finite difference step, relative to `max(1, |x|)`.
+    pub step: f64,
+    /// Points closer than this to a singularity of `Log`, `Tan` or a
+    /// negative `Pow` are skipped.
+    pub margin: f64,
+    pub seed: u64,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            samples: 200,
+            low: -10.0,
+            high: 10.0,
+            step: 1e-5,
+            margin: 0.05,
+            seed: 0x5eed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyReport {
+    pub worst_relative_error: f64,
+    pub worst_at: Option<f64>,
+    pub checked: usize,
+    pub skipped: usize,
+}
+
+impl VerifyReport {
+    pub fn passes(&self, tolerance: f64) -> bool {
+        self.checked > 0 && self.worst_relative_error <= tolerance
+    }
+}
+
+/// Compares `expression.diff()` against central finite differences of
+/// `expression`.
+pub fn check_derivative(expression: &Expression, options: &VerifyOptions) -> VerifyReport {
+    compare(expression, &expression.diff(), options)
+}
+
+/// Compares a claimed `derivative` of `expression` against central finite
+/// differences of `expression`.
+pub fn compare(
+    expression: &Expression,
+    derivative: &Expression,
+    options: &VerifyOptions,
+) -> VerifyReport {
+    let f = expression.compile();
+    let df = derivative.compile();
+    let mut rng = Rng::new(options.seed);
+    let mut report = VerifyReport {
+        worst_relative_error: 0.0,
+        worst_at: None,
+        checked: 0,
+        skipped: 0,
+    };
+    for _ in 0..options.samples {
+        let x = rng.range(options.low, options.high);
+        let h = options.step * x.abs().max(1.0);
+        if near_singularity(expression, x, options.margin + h) {
+            report.skipped += 1;
+            continue;
+        }
+        let values = f
+            .call(x + h)
+            .and_then(|forward| Ok((forward, f.call(x - h)?, df.call(x)?)));
+        let (forward, backward, exact) = match values {
+            Ok(values) if [values.0, values.1, values.2].iter().all(|v| v.is_finite()) => values,
+            _ => {
+                report.skipped += 1;
+                continue;
+            }
+        };
+        let approximate = (forward - backward) / (2.0 * h);
+        let error = (approximate - exact).abs() / exact.abs().max(1.0);
+        report.checked += 1;
+        if report.worst_at.is_none() || error > report.worst_relative_error {
+            report.worst_relative_error = error;
+            report.worst_at = Some(x);
+        }
+    }
+    report
+}
+
+fn near_singularity(expression: &Expression, x: f64, margin: f64) -> bool {
+    match expression {
+        Expression::Base(base) => match base {
+            Base::Scaler(_) | Base::X(_) => false,
+            Base::Exp(exp) => near_singularity(&exp.arg, x, margin),
+            Base::Pow(pow) => {
+                near_singularity(&pow.arg, x, margin)
+                    || (pow.exp < 0 && pow.arg.eval(x).abs() <= margin)
+            }
+            Base::Log(log) => near_singularity(&log.arg, x, margin) || log.arg.eval(x) <= margin,
+            Base::Sin(sin) => near_singularity(&sin.arg, x, margin),
+            Base::Cos(cos) => near_singularity(&cos.arg, x, margin),
+            Base::Tan(tan) => {
+                near_singularity(&tan.arg, x, margin) || tan.arg.eval(x).cos().abs() <= margin
+            }
+            Base::Custom(custom) => near_singularity(&custom.arg, x, margin),
+            Base::Apply(apply) => apply.args.iter().any(|e| near_singularity(e, x, margin)),
+            Base::Derivative(derivative) => near_singularity(&derivative.func, x, margin),
+        },
+        Expression::Add(add) => add.iter().any(|e| near_singularity(e, x, margin)),
+        Expression::Mul(mul) => mul.iter().any(|e| near_singularity(e, x, margin)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{cos, exp, log, pow, sin, tan, x};
+    use crate::verify::{check_derivative, compare, VerifyOptions};
+
+    #[test]
+    fn test_check_derivative() {
+        let cases = [
+            pow(x(), -3),
+            tan(log(x())),
+            sin(cos(x())),
+            x() * exp(sin(x())) * log(pow(x(), 2)),
+        ];
+
+        for case in cases {
+            let report = check_derivative(&case, &VerifyOptions::default());
+            assert!(report.passes(1e-5), "{:?}: {:?}", case, report);
+        }
+    }
+
+    #[test]
+    fn test_compare_wrong_derivative() {
+        let report = compare(&log(x()), &pow(x(), -2), &VerifyOptions::default());
+
+        assert!(!report.passes(1e-5));
+        assert!(report.skipped > 0);
+        assert!(report.worst_at.unwrap() > 0.0);
+    }
+}
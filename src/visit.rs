@@ -0,0 +1,214 @@
+//! Generic traversals of an `Expression` tree.
+//!
+//! A `Visitor` walks a tree by reference and a `Fold` builds a new tree from
+//! an old one. Both recurse into every child of `Add` and `Mul` and into the
+//! `arg` of each `Base` function by default, so an implementation only
+//! overrides the methods for the nodes it cares about. An overriding method
+//! can still recurse by calling the matching free function, e.g.
+//! `walk_base(self, base)`.
+
+use crate::{Apply, Base, Cos, Custom, Derivative, Exp, Expression, Log, Pow, Sin, Tan};
+
+pub trait Visitor {
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression)
+    }
+
+    fn visit_add(&mut self, terms: &[Expression]) {
+        for term in terms {
+            self.visit_expression(term);
+        }
+    }
+
+    fn visit_mul(&mut self, factors: &[Expression]) {
+        for factor in factors {
+            self.visit_expression(factor);
+        }
+    }
+
+    fn visit_base(&mut self, base: &Base) {
+        walk_base(self, base)
+    }
+}
+
+/// Dispatches `expression` to the `Visitor` method for its kind.
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match expression {
+        Expression::Base(base) => visitor.visit_base(base),
+        Expression::Add(terms) => visitor.visit_add(terms),
+        Expression::Mul(factors) => visitor.visit_mul(factors),
+    }
+}
+
+/// Visits the argument of `base`, if it has one.
+pub fn walk_base<V: Visitor + ?Sized>(visitor: &mut V, base: &Base) {
+    match base {
+        Base::Scaler(_) | Base::X(_) => {}
+        Base::Exp(exp) => visitor.visit_expression(&exp.arg),
+        Base::Pow(pow) => visitor.visit_expression(&pow.arg),
+        Base::Log(log) => visitor.visit_expression(&log.arg),
+        Base::Sin(sin) => visitor.visit_expression(&sin.arg),
+        Base::Cos(cos) => visitor.visit_expression(&cos.arg),
+        Base::Tan(tan) => visitor.visit_expression(&tan.arg),
+        Base::Custom(custom) => visitor.visit_expression(&custom.arg),
+        Base::Apply(apply) => {
+            for arg in &apply.args {
+                visitor.visit_expression(arg);
+            }
+        }
+        Base::Derivative(derivative) => visitor.visit_expression(&derivative.func),
+    }
+}
+
+pub trait Fold {
+    fn fold_expression(&mut self, expression: &Expression) -> Expression {
+        fold_expression(self, expression)
+    }
+
+    fn fold_add(&mut self, terms: &[Expression]) -> Expression {
+        fold_add(self, terms)
+    }
+
+    fn fold_mul(&mut self, factors: &[Expression]) -> Expression {
+        fold_mul(self, factors)
+    }
+
+    fn fold_base(&mut self, base: &Base) -> Expression {
+        fold_base(self, base)
+    }
+}
+
+/// Dispatches `expression` to the `Fold` method for its kind.
+pub fn fold_expression<F: Fold + ?Sized>(folder: &mut F, expression: &Expression) -> Expression {
+    match expression {
+        Expression::Base(base) => folder.fold_base(base),
+        Expression::Add(terms) => folder.fold_add(terms),
+        Expression::Mul(factors) => folder.fold_mul(factors),
+    }
+}
+
+/// Rebuilds the sum with every term folded.
+pub fn fold_add<F: Fold + ?Sized>(folder: &mut F, terms: &[Expression]) -> Expression {
+    Expression::Add(terms.iter().map(|e| folder.fold_expression(e)).collect())
+}
+
+/// Rebuilds the product with every factor folded.
+pub fn fold_mul<F: Fold + ?Sized>(folder: &mut F, factors: &[Expression]) -> Expression {
+    Expression::Mul(factors.iter().map(|e| folder.fold_expression(e)).collect())
+}
+
+/// Rebuilds `base` with its argument folded.
+pub fn fold_base<F: Fold + ?Sized>(folder: &mut F, base: &Base) -> Expression {
+    let mut arg = |arg: &Expression| Box::new(folder.fold_expression(arg));
+    Expression::Base(match base {
+        Base::Scaler(scaler) => Base::Scaler(*scaler),
+        Base::X(x) => Base::X(x.clone()),
+        Base::Exp(exp) => Base::Exp(Exp { arg: arg(&exp.arg) }),
+        Base::Pow(pow) => Base::Pow(Pow {
+            exp: pow.exp,
+            arg: arg(&pow.arg),
+        }),
+        Base::Log(log) => Base::Log(Log { arg: arg(&log.arg) }),
+        Base::Sin(sin) => Base::Sin(Sin { arg: arg(&sin.arg) }),
+        Base::Cos(cos) => Base::Cos(Cos { arg: arg(&cos.arg) }),
+        Base::Tan(tan) => Base::Tan(Tan { arg: arg(&tan.arg) }),
+        Base::Custom(custom) => Base::Custom(Custom {
+            name: custom.name.clone(),
+            arg: arg(&custom.arg),
+        }),
+        Base::Apply(apply) => Base::Apply(Apply {
+            name: apply.name.clone(),
+            args: apply.args.iter().map(|e| *arg(e)).collect(),
+        }),
+        Base::Derivative(derivative) => Base::Derivative(Derivative {
+            func: arg(&derivative.func),
+            order: derivative.order,
+            wrt: derivative.wrt,
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::{cos, exp, log, pow, sin, tan, x};
+    use crate::visit::{fold_base, walk_base, Fold, Visitor};
+    use crate::{Base, Expression};
+
+    struct Identity;
+
+    impl Fold for Identity {}
+
+    // Counts the nodes of each kind.
+    #[derive(Default)]
+    struct Count {
+        bases: usize,
+        functions: usize,
+        sums: usize,
+    }
+
+    impl Visitor for Count {
+        fn visit_add(&mut self, terms: &[Expression]) {
+            self.sums += 1;
+            for term in terms {
+                self.visit_expression(term);
+            }
+        }
+
+        fn visit_base(&mut self, base: &Base) {
+            self.bases += 1;
+            if !matches!(base, Base::Scaler(_) | Base::X(_)) {
+                self.functions += 1;
+            }
+            walk_base(self, base);
+        }
+    }
+
+    // Replaces `sin` by `cos` and doubles every constant.
+    struct Swap;
+
+    impl Fold for Swap {
+        fn fold_base(&mut self, base: &Base) -> Expression {
+            match base {
+                Base::Scaler(n) => Expression::from(2 * n),
+                Base::Sin(sin) => cos(self.fold_expression(&sin.arg)),
+                base => fold_base(self, base),
+            }
+        }
+    }
+
+    #[test]
+    fn test_identity_fold() {
+        let cases = [
+            x(),
+            Expression::from(-4),
+            sin(pow(x(), 2)) * exp(x()) + 3,
+            tan(log(x() + 1)) - cos(x()),
+        ];
+
+        for case in cases {
+            assert_eq!(Identity.fold_expression(&case), case);
+        }
+    }
+
+    #[test]
+    fn test_visitor() {
+        let mut count = Count::default();
+        count.visit_expression(&(sin(pow(x() + 1, 2)) * exp(x()) + 3));
+
+        assert_eq!((count.bases, count.functions, count.sums), (7, 3, 2));
+    }
+
+    #[test]
+    fn test_fold() {
+        let cases = [
+            (sin(x()), cos(x())),
+            (3 * sin(sin(x())), 6 * cos(cos(x()))),
+            (exp(x() + 1), exp(x() + 2)),
+            (pow(sin(x()), 3), pow(cos(x()), 3)),
+        ];
+
+        for case in cases {
+            assert_eq!(Swap.fold_expression(&case.0), case.1);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use diff_rs::build::{exp, x};
+use diff_rs::compile::DomainError;
+use diff_rs::equivalence::Equivalence;
+use diff_rs::limit::Point;
+use diff_rs::parse::parse;
+use diff_rs::solve::{newton, NewtonOptions};
+use diff_rs::substitute::compose;
+use diff_rs::verify::{check_derivative, VerifyOptions};
+use diff_rs::{Base, Cos, Expression, Log, Pow, Sin, X};
+
+#[test]
+fn test_constructors() {
+    let cases = [
+        (Expression::from(X {}), Expression::Base(Base::X(X {}))),
+        (
+            Expression::from(Sin::new(Expression::from(X {}))),
+            Expression::Base(Base::Sin(Sin {
+                arg: Box::new(Expression::Base(Base::X(X {}))),
+            })),
+        ),
+        (
+            Expression::from(Pow::new(-2, Expression::from(Base::Scaler(3)))),
+            Expression::Base(Base::Pow(Pow {
+                exp: -2,
+                arg: Box::new(Expression::Base(Base::Scaler(3))),
+            })),
+        ),
+    ];
+
+    for case in cases {
+        assert_eq!(case.0, case.1);
+    }
+}
+
+#[test]
+fn test_diff() {
+    let expression = x() * exp(x());
+
+    assert_eq!(expression.diff(), 1 * exp(x()) + x() * (exp(x()) * 1));
+    assert_eq!(
+        expression.diff().simplify().to_string(),
+        "exp(x) + x * exp(x)"
+    );
+}
+
+#[test]
+fn test_parse_and_display() {
+    let expression: Expression = "sin(x^2) / x".parse().unwrap();
+
+    assert_eq!(expression.to_string(), "sin(x^2) / x");
+    assert_eq!(
+        parse("x +").unwrap_err().to_string(),
+        "unexpected end of input at column 4"
+    );
+}
+
+#[test]
+fn test_numeric() {
+    let expression = parse("x^2 - 2").unwrap();
+    let root = newton(&expression, 1.0, &NewtonOptions::default())
+        .root
+        .unwrap();
+    assert!((root - 2.0_f64.sqrt()).abs() < 1e-12);
+
+    assert_eq!(
+        parse("sin(x) / x").unwrap().limit(Point::Finite(0.0)),
+        Ok(1.0)
+    );
+    assert_eq!(
+        Expression::from(Log::new(Expression::from(X {})))
+            .compile()
+            .call(0.0),
+        Err(DomainError {
+            function: "log",
+            arg: 0.0,
+        })
+    );
+    assert!(check_derivative(
+        &parse("tan(cos(x)) * exp(x)").unwrap(),
+        &VerifyOptions::default()
+    )
+    .passes(1e-5));
+}
+
+#[test]
+fn test_symbolic() {
+    let outer = Expression::from(Cos::new(Expression::from(X {})));
+    let inner = parse("2x + 1").unwrap();
+
+    assert_eq!(compose(&outer, &inner).to_string(), "cos(2 * x + 1)");
+    assert_eq!(
+        parse("x * 2").unwrap().equivalent(&parse("2 * x").unwrap()),
+        Equivalence::Equal
+    );
+}
@@ -0,0 +1,70 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const BINARY: &str = env!("CARGO_BIN_EXE_diff_rs");
+
+#[test]
+fn test_overflow_is_an_error() {
+    let cases = [
+        vec!["--order", "2", "x^-9223372036854775807"],
+        vec!["--simplify", "--order", "2", "x^-9223372036854775807"],
+    ];
+
+    for case in cases {
+        let output = Command::new(BINARY).args(&case).output().unwrap();
+        assert_eq!(output.status.code(), Some(4), "{:?}", case);
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            "error: exponent -9223372036854775808 - 1 overflows\n"
+        );
+    }
+}
+
+#[test]
+fn test_batch_overflow_is_an_error_record() {
+    let mut child = Command::new(BINARY)
+        .args(["--order", "2", "--jobs", "2", "--batch", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"x^-9223372036854775807\nx^3\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(output.status.code(), Some(4));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(
+        lines[0],
+        "error: exponent -9223372036854775808 - 1 overflows"
+    );
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_repl_overflow_is_an_error() {
+    let mut child = Command::new(BINARY)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"x^-9223372036854775807\n:diff 2\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("error: exponent -9223372036854775808 - 1 overflows"));
+}